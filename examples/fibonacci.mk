@@ -0,0 +1,11 @@
+let fibonacci = fn(n) {
+  if (n < 2) {
+    return n;
+  }
+  fibonacci(n - 1) + fibonacci(n - 2)
+};
+
+puts("the first fibonacci numbers:");
+puts(map([0, 1, 2, 3, 4, 5, 6, 7, 8, 9], fibonacci));
+
+fibonacci(15)
@@ -1,56 +1,571 @@
-use crate::lexer::Token;
+use std::fmt;
+use std::ops::Deref;
 
-trait Node {
-    // fn token_literal(&self) -> &Token;
+use crate::lexer::{LimiterToken, Span, Token};
+
+pub trait Node {
+    /// The token the node starts with, or for operations the operator's.
+    fn token_literal(&self) -> &Token;
+}
+
+/// A node along with where in the source it came from.
+#[derive(Clone, Debug)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+}
+
+/// For nodes that weren't parsed from anything, like the ones built by hand in tests.
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Self::new(node, Span::default())
+    }
+}
+
+/// Only the nodes are compared, so the same code laid out differently still gives equal trees.
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Spanned<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.node == *other
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.node.fmt(f)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Statement {
-    Let(Expression, Expression),
-    Return(Expression),
+    Let(Spanned<Expression>, Spanned<Expression>),
+    /// `None` for a bare `return;`, which returns null.
+    Return(Option<Spanned<Expression>>),
     Expression(Expression),
+    Block(Vec<Spanned<Statement>>),
+    /// A statement rather than an expression, since a loop has no value to give back.
+    While {
+        condition: Spanned<Expression>,
+        body: Box<Spanned<Statement>>,
+    },
+    /// A C-style loop. Any of the clauses in the header can be left out.
+    For {
+        init: Option<Box<Spanned<Statement>>>,
+        condition: Option<Spanned<Expression>>,
+        post: Option<Box<Spanned<Statement>>>,
+        body: Box<Spanned<Statement>>,
+    },
 }
 
-impl Statement {
-    fn statement_node(&self) {
-        todo!()
+/// Writes the statement back as Monkey source, so parsing the output gives the same AST.
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Statement::Let(ident, value) => write!(f, "let {} = {};", ident, value),
+            Statement::Return(Some(value)) => write!(f, "return {};", value),
+            Statement::Return(None) => write!(f, "return;"),
+            Statement::Expression(e) => write!(f, "{};", e),
+            Statement::Block(statements) if statements.is_empty() => write!(f, "{{}}"),
+            Statement::Block(statements) => write!(f, "{{ {} }}", join(statements, " ")),
+            Statement::While { condition, body } => write!(f, "while ({}) {}", condition, body),
+            Statement::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                write!(f, "for (")?;
+                if let Some(init) = init {
+                    write_clause(f, init)?;
+                }
+                write!(f, ";")?;
+                if let Some(condition) = condition {
+                    write!(f, " {}", condition)?;
+                }
+                write!(f, ";")?;
+                if let Some(post) = post {
+                    write!(f, " ")?;
+                    write_clause(f, post)?;
+                }
+                write!(f, ") {}", body)
+            }
+        }
+    }
+}
+
+/// A statement in a for loop header, where the `;` are separators rather than part of it.
+fn write_clause(f: &mut fmt::Formatter, statement: &Statement) -> fmt::Result {
+    match statement {
+        Statement::Let(ident, value) => write!(f, "let {} = {}", ident, value),
+        Statement::Expression(e) => write!(f, "{}", e),
+        s => write!(f, "{}", s),
     }
 }
 
 impl Node for Statement {
-    // fn token_literal(&self) -> &Token {
-    //     return match self {
-    //         Statement::Let(t, _, _) => t,
-    //     };
-    // }
+    fn token_literal(&self) -> &Token {
+        match self {
+            Statement::Let(_, _) => &Token::Let,
+            Statement::Return(_) => &Token::Return,
+            Statement::Expression(e) => e.token_literal(),
+            Statement::Block(_) => &Token::Limiter(LimiterToken::LBrace),
+            Statement::While { .. } => &Token::While,
+            Statement::For { .. } => &Token::For,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expression {
     Identifier(Token),
+    /// `token` is the literal as written, `value` what it stands for.
+    IntegerLiteral {
+        token: Token,
+        value: i64,
+    },
+    Boolean(bool),
+    Null,
+    StringLiteral(Token),
+    Prefix {
+        operator: Token,
+        right: Box<Spanned<Expression>>,
+    },
+    Infix {
+        left: Box<Spanned<Expression>>,
+        operator: Token,
+        right: Box<Spanned<Expression>>,
+    },
+    ArrayLiteral(Vec<Spanned<Expression>>),
+    HashLiteral(Vec<(Spanned<Expression>, Spanned<Expression>)>),
+    /// `alternative` is a block, or for an `else if` an expression statement with the next if.
+    If {
+        condition: Box<Spanned<Expression>>,
+        consequence: Box<Spanned<Statement>>,
+        alternative: Option<Box<Spanned<Statement>>>,
+    },
+    FunctionLiteral {
+        parameters: Vec<String>,
+        body: Box<Spanned<Statement>>,
+    },
+    /// Parsed like a function literal, expanding it is yet to come.
+    MacroLiteral {
+        parameters: Vec<String>,
+        body: Box<Spanned<Statement>>,
+    },
+    Call {
+        function: Box<Spanned<Expression>>,
+        arguments: Vec<Spanned<Expression>>,
+    },
+    Index {
+        left: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+    },
+    /// Rebinds an existing name, `target` being an identifier or an index expression.
+    Assign {
+        target: Box<Spanned<Expression>>,
+        value: Box<Spanned<Expression>>,
+    },
 }
 
 impl Expression {
-    fn expression_node(&self) {
-        todo!()
+    /// An integer literal written out the plain way, for building trees by hand.
+    pub fn integer(value: i64) -> Self {
+        Expression::IntegerLiteral {
+            token: Token::Literal(value.to_string()),
+            value,
+        }
     }
 }
 
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Identifier(t) => write!(f, "{}", t),
+            Expression::IntegerLiteral { value, .. } => write!(f, "{}", value),
+            Expression::Boolean(b) => write!(f, "{}", b),
+            Expression::Null => write!(f, "null"),
+            Expression::StringLiteral(t) => write!(f, "{}", t),
+            // always parenthesized so the output doesn't depend on precedence
+            Expression::Prefix { operator, right } => write!(f, "({}{})", operator, right),
+            Expression::Infix {
+                left,
+                operator,
+                right,
+            } => write!(f, "({} {} {})", left, operator, right),
+            Expression::ArrayLiteral(elements) => write!(f, "[{}]", join(elements, ", ")),
+            Expression::HashLiteral(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                write!(f, "if ({}) {}", condition, consequence)?;
+                match alternative.as_deref().map(Deref::deref) {
+                    Some(Statement::Expression(chained)) => write!(f, " else {}", chained),
+                    Some(alternative) => write!(f, " else {}", alternative),
+                    None => Ok(()),
+                }
+            }
+            Expression::FunctionLiteral { parameters, body } => {
+                write!(f, "fn({}) {}", join(parameters, ", "), body)
+            }
+            Expression::MacroLiteral { parameters, body } => {
+                write!(f, "macro({}) {}", join(parameters, ", "), body)
+            }
+            Expression::Call {
+                function,
+                arguments,
+            } => write!(f, "{}({})", function, join(arguments, ", ")),
+            Expression::Index { left, index } => write!(f, "{}[{}]", left, index),
+            Expression::Assign { target, value } => write!(f, "({} = {})", target, value),
+        }
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T], separator: &str) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
 impl Node for Expression {
-    // fn token_literal(&self) -> &Token {
-    //     return match self {
-    //         Expression::Identifier(t) => t,
-    //     };
-    // }
+    fn token_literal(&self) -> &Token {
+        match self {
+            Expression::Identifier(t) | Expression::StringLiteral(t) => t,
+            Expression::IntegerLiteral { token, .. } => token,
+            Expression::Boolean(true) => &Token::True,
+            Expression::Boolean(false) => &Token::False,
+            Expression::Null => &Token::Null,
+            Expression::Prefix { operator, .. } | Expression::Infix { operator, .. } => operator,
+            Expression::ArrayLiteral(_) => &Token::Limiter(LimiterToken::LBracket),
+            Expression::HashLiteral(_) => &Token::Limiter(LimiterToken::LBrace),
+            Expression::If { .. } => &Token::If,
+            Expression::FunctionLiteral { .. } => &Token::Function,
+            Expression::MacroLiteral { .. } => &Token::Macro,
+            Expression::Call { .. } => &Token::Limiter(LimiterToken::LParen),
+            Expression::Index { .. } => &Token::Limiter(LimiterToken::LBracket),
+            Expression::Assign { .. } => &Token::Assign,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Program {
-    pub statements: Vec<Statement>,
+    pub statements: Vec<Spanned<Statement>>,
 }
 
 impl Program {
     pub fn new() -> Self {
-        return Self { statements: vec![] };
+        Self { statements: vec![] }
+    }
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
     }
 }
+
+/// One statement per line.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", join(&self.statements, "\n"))
+    }
+}
+
+/// Walks an AST. Every method recurses into the node's children by default, so implementors only
+/// override the ones for the nodes they care about, calling the matching `walk_*` function to
+/// keep going below them.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Let(ident, value) => {
+            visitor.visit_expression(ident);
+            visitor.visit_expression(value);
+        }
+        Statement::Return(Some(value)) => visitor.visit_expression(value),
+        Statement::Return(None) => (),
+        Statement::Expression(e) => visitor.visit_expression(e),
+        Statement::Block(statements) => {
+            for s in statements {
+                visitor.visit_statement(s);
+            }
+        }
+        Statement::While { condition, body } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(body);
+        }
+        Statement::For {
+            init,
+            condition,
+            post,
+            body,
+        } => {
+            if let Some(init) = init {
+                visitor.visit_statement(init);
+            }
+            if let Some(condition) = condition {
+                visitor.visit_expression(condition);
+            }
+            if let Some(post) = post {
+                visitor.visit_statement(post);
+            }
+            visitor.visit_statement(body);
+        }
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::IntegerLiteral { .. }
+        | Expression::Boolean(_)
+        | Expression::Null
+        | Expression::StringLiteral(_) => (),
+        Expression::Prefix { right, .. } => visitor.visit_expression(right),
+        Expression::Infix { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::ArrayLiteral(elements) => {
+            for e in elements {
+                visitor.visit_expression(e);
+            }
+        }
+        Expression::HashLiteral(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(consequence);
+            if let Some(alternative) = alternative {
+                visitor.visit_statement(alternative);
+            }
+        }
+        Expression::FunctionLiteral { body, .. } | Expression::MacroLiteral { body, .. } => {
+            visitor.visit_statement(body)
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            visitor.visit_expression(function);
+            for a in arguments {
+                visitor.visit_expression(a);
+            }
+        }
+        Expression::Index { left, index } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(index);
+        }
+        Expression::Assign { target, value } => {
+            visitor.visit_expression(target);
+            visitor.visit_expression(value);
+        }
+    }
+}
+
+/// Collects the name of every identifier in the tree, in the order they appear.
+#[derive(Default)]
+pub struct IdentifierCollector {
+    pub names: Vec<String>,
+}
+
+impl Visitor for IdentifierCollector {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::Identifier(Token::Identifier(name)) = expression {
+            self.names.push(name.to_string());
+        }
+        walk_expression(self, expression);
+    }
+}
+
+#[test]
+fn token_literal_test() {
+    let ident = Expression::Identifier(Token::Identifier("x".into()));
+    let tests = [
+        (
+            Statement::Let(
+                ident.clone().into(),
+                Expression::StringLiteral(Token::StringLiteral(String::from("a"))).into(),
+            ),
+            Token::Let,
+        ),
+        (Statement::Return(Some(ident.clone().into())), Token::Return),
+        (
+            Statement::Expression(ident.clone()),
+            Token::Identifier("x".into()),
+        ),
+        (
+            Statement::Expression(Expression::integer(5)),
+            Token::Literal(String::from("5")),
+        ),
+        (
+            Statement::Expression(Expression::StringLiteral(Token::StringLiteral(
+                String::from("a"),
+            ))),
+            Token::StringLiteral(String::from("a")),
+        ),
+        (
+            Statement::Expression(Expression::Call {
+                function: Box::new(ident.clone().into()),
+                arguments: vec![],
+            }),
+            Token::Limiter(LimiterToken::LParen),
+        ),
+        (
+            Statement::Expression(Expression::FunctionLiteral {
+                parameters: vec![],
+                body: Box::new(Statement::Block(vec![]).into()),
+            }),
+            Token::Function,
+        ),
+        (
+            Statement::Expression(Expression::MacroLiteral {
+                parameters: vec![],
+                body: Box::new(Statement::Block(vec![]).into()),
+            }),
+            Token::Macro,
+        ),
+        (
+            Statement::Block(vec![]),
+            Token::Limiter(LimiterToken::LBrace),
+        ),
+        (
+            Statement::While {
+                condition: ident.clone().into(),
+                body: Box::new(Statement::Block(vec![]).into()),
+            },
+            Token::While,
+        ),
+    ];
+
+    for (node, expected) in tests {
+        assert_eq!(node.token_literal(), &expected);
+    }
+}
+
+#[test]
+fn identifier_collector_test() {
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let program = Program {
+        statements: vec![
+            Statement::Let(
+                ident("f").into(),
+                Expression::FunctionLiteral {
+                    parameters: vec![String::from("x")],
+                    body: Box::new(
+                        Statement::Block(vec![Statement::Return(Some(
+                            Expression::Index {
+                                left: Box::new(ident("x").into()),
+                                index: Box::new(
+                                    Expression::StringLiteral(Token::StringLiteral(String::from(
+                                        "k",
+                                    )))
+                                    .into(),
+                                ),
+                            }
+                            .into(),
+                        ))
+                        .into()])
+                        .into(),
+                    ),
+                }
+                .into(),
+            )
+            .into(),
+            Statement::Expression(Expression::If {
+                condition: Box::new(ident("c").into()),
+                consequence: Box::new(
+                    Statement::Block(vec![Statement::Expression(Expression::Call {
+                        function: Box::new(ident("f").into()),
+                        arguments: vec![Expression::HashLiteral(vec![(
+                            ident("k").into(),
+                            Expression::ArrayLiteral(vec![ident("v").into()]).into(),
+                        )])
+                        .into()],
+                    })
+                    .into()])
+                    .into(),
+                ),
+                alternative: None,
+            })
+            .into(),
+        ],
+    };
+
+    let mut collector = IdentifierCollector::default();
+    collector.visit_program(&program);
+    assert_eq!(collector.names, ["f", "x", "c", "f", "k", "v"]);
+}
+
+#[test]
+fn spanned_equality_test() {
+    use crate::lexer::Position;
+
+    let somewhere = Span {
+        start: Position { line: 2, column: 3 },
+        end: Position { line: 2, column: 4 },
+    };
+    let parsed = Spanned::new(Expression::integer(1), somewhere);
+    let built: Spanned<Expression> = Expression::integer(1).into();
+
+    assert_eq!(parsed, built);
+    assert_eq!(parsed, Expression::integer(1));
+    assert_ne!(parsed, Expression::integer(2));
+    assert_eq!(built.span, Span::default());
+    assert_eq!(parsed.to_string(), "1");
+}
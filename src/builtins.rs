@@ -0,0 +1,533 @@
+use std::io::{self, Write};
+
+use crate::evaluator::apply_function;
+use crate::object::{Builtin, Object};
+
+/// The functions every program can call without defining them.
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "len",
+        function: builtin_len,
+    },
+    Builtin {
+        name: "puts",
+        function: builtin_puts,
+    },
+    Builtin {
+        name: "first",
+        function: builtin_first,
+    },
+    Builtin {
+        name: "last",
+        function: builtin_last,
+    },
+    Builtin {
+        name: "rest",
+        function: builtin_rest,
+    },
+    Builtin {
+        name: "push",
+        function: builtin_push,
+    },
+    Builtin {
+        name: "type",
+        function: builtin_type,
+    },
+    Builtin {
+        name: "int",
+        function: builtin_int,
+    },
+    Builtin {
+        name: "str",
+        function: builtin_str,
+    },
+    Builtin {
+        name: "map",
+        function: builtin_map,
+    },
+    Builtin {
+        name: "filter",
+        function: builtin_filter,
+    },
+    Builtin {
+        name: "reduce",
+        function: builtin_reduce,
+    },
+];
+
+pub fn get(name: &str) -> Option<Object> {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name == name)
+        .map(|builtin| Object::Builtin(*builtin))
+}
+
+fn wrong_number_of_arguments(expected: usize, got: usize) -> Object {
+    Object::Error(format!(
+        "wrong number of arguments: expected {}, got {}",
+        expected, got
+    ))
+}
+
+/// The elements of the first argument, or the error to return when it isn't an array.
+fn array_argument<'a>(name: &str, arg: &'a Object) -> Result<&'a [Object], Object> {
+    match arg {
+        Object::Array(elements) => Ok(elements),
+        arg => Err(Object::Error(format!(
+            "argument to `{}` must be ARRAY, got {}",
+            name,
+            arg.type_name()
+        ))),
+    }
+}
+
+/// The argument itself if it can be called, or the error to return when it can't.
+fn function_argument(name: &str, arg: Object) -> Result<Object, Object> {
+    match arg {
+        Object::Function { .. } | Object::Builtin(_) => Ok(arg),
+        arg => Err(Object::Error(format!(
+            "argument to `{}` must be FUNCTION, got {}",
+            name,
+            arg.type_name()
+        ))),
+    }
+}
+
+/// The number of bytes in a string, as UTF-8, or elements in an array.
+pub fn builtin_len(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match &args[0] {
+        Object::String(s) => Object::Integer(s.len() as i64),
+        Object::Array(elements) => Object::Integer(elements.len() as i64),
+        arg => Object::Error(format!(
+            "argument to `len` not supported, got {}",
+            arg.type_name()
+        )),
+    }
+}
+
+/// The first element of an array, or null when it's empty.
+pub fn builtin_first(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match array_argument("first", &args[0]) {
+        Ok(elements) => elements.first().cloned().unwrap_or(Object::Null),
+        Err(e) => e,
+    }
+}
+
+/// The last element of an array, or null when it's empty.
+pub fn builtin_last(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match array_argument("last", &args[0]) {
+        Ok(elements) => elements.last().cloned().unwrap_or(Object::Null),
+        Err(e) => e,
+    }
+}
+
+/// A new array with every element but the first, or null when it's empty.
+pub fn builtin_rest(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match array_argument("rest", &args[0]) {
+        Ok([]) => Object::Null,
+        Ok([_, rest @ ..]) => Object::Array(rest.to_vec()),
+        Err(e) => e,
+    }
+}
+
+/// A new array with the second argument added to the end of the first.
+pub fn builtin_push(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(2, args.len());
+    }
+
+    match array_argument("push", &args[0]) {
+        Ok(elements) => {
+            let mut elements = elements.to_vec();
+            elements.push(args[1].clone());
+            Object::Array(elements)
+        }
+        Err(e) => e,
+    }
+}
+
+/// The name of the argument's type, as used in error messages.
+pub fn builtin_type(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    Object::String(args[0].type_name().to_string())
+}
+
+/// Converts a string holding a decimal integer or a boolean to an integer.
+pub fn builtin_int(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match &args[0] {
+        Object::Integer(n) => Object::Integer(*n),
+        Object::Boolean(b) => Object::Integer(*b as i64),
+        Object::String(s) => match s.trim().parse() {
+            Ok(n) => Object::Integer(n),
+            Err(_) => Object::Error(format!("cannot convert {:?} to INTEGER", s)),
+        },
+        arg => Object::Error(format!(
+            "argument to `int` not supported, got {}",
+            arg.type_name()
+        )),
+    }
+}
+
+/// Converts an integer or a boolean to the string it's written as.
+pub fn builtin_str(args: Vec<Object>, _: usize) -> Object {
+    if args.len() != 1 {
+        return wrong_number_of_arguments(1, args.len());
+    }
+
+    match &args[0] {
+        Object::Integer(n) => Object::String(n.to_string()),
+        Object::Boolean(b) => Object::String(b.to_string()),
+        Object::String(s) => Object::String(s.clone()),
+        arg => Object::Error(format!(
+            "argument to `str` not supported, got {}",
+            arg.type_name()
+        )),
+    }
+}
+
+/// A new array with the result of calling the function on each element of the array.
+pub fn builtin_map(args: Vec<Object>, calls_left: usize) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(2, args.len());
+    }
+
+    let elements = match array_argument("map", &args[0]) {
+        Ok(elements) => elements,
+        Err(e) => return e,
+    };
+    let function = match function_argument("map", args[1].clone()) {
+        Ok(function) => function,
+        Err(e) => return e,
+    };
+
+    let mut mapped = Vec::with_capacity(elements.len());
+    for element in elements {
+        match apply_function(function.clone(), vec![element.clone()], calls_left) {
+            Object::Error(e) => return Object::Error(e),
+            result => mapped.push(result),
+        }
+    }
+    Object::Array(mapped)
+}
+
+/// A new array with the elements of the array the function returns something truthy for.
+pub fn builtin_filter(args: Vec<Object>, calls_left: usize) -> Object {
+    if args.len() != 2 {
+        return wrong_number_of_arguments(2, args.len());
+    }
+
+    let elements = match array_argument("filter", &args[0]) {
+        Ok(elements) => elements,
+        Err(e) => return e,
+    };
+    let function = match function_argument("filter", args[1].clone()) {
+        Ok(function) => function,
+        Err(e) => return e,
+    };
+
+    let mut kept = vec![];
+    for element in elements {
+        match apply_function(function.clone(), vec![element.clone()], calls_left) {
+            Object::Error(e) => return Object::Error(e),
+            result if result.is_truthy() => kept.push(element.clone()),
+            _ => (),
+        }
+    }
+    Object::Array(kept)
+}
+
+/// Folds the array into a single value, calling the function with the value so far, starting
+/// with the initial one, and each element in turn.
+pub fn builtin_reduce(args: Vec<Object>, calls_left: usize) -> Object {
+    if args.len() != 3 {
+        return wrong_number_of_arguments(3, args.len());
+    }
+
+    let elements = match array_argument("reduce", &args[0]) {
+        Ok(elements) => elements,
+        Err(e) => return e,
+    };
+    let function = match function_argument("reduce", args[2].clone()) {
+        Ok(function) => function,
+        Err(e) => return e,
+    };
+
+    let mut accumulator = args[1].clone();
+    for element in elements {
+        accumulator = apply_function(
+            function.clone(),
+            vec![accumulator, element.clone()],
+            calls_left,
+        );
+        if let Object::Error(_) = accumulator {
+            return accumulator;
+        }
+    }
+    accumulator
+}
+
+/// Prints each argument on its own line.
+pub fn builtin_puts(args: Vec<Object>, _: usize) -> Object {
+    puts(&mut io::stdout(), &args)
+}
+
+fn puts(out: &mut impl Write, args: &[Object]) -> Object {
+    for arg in args {
+        if let Err(e) = writeln!(out, "{}", arg) {
+            return Object::Error(format!("puts failed: {}", e));
+        }
+    }
+
+    Object::Null
+}
+
+#[test]
+fn builtin_len_test() {
+    let tests = [
+        (
+            vec![Object::String(String::from("hello"))],
+            Object::Integer(5),
+        ),
+        (vec![Object::String(String::new())], Object::Integer(0)),
+        (
+            vec![Object::String(String::from("olá"))],
+            Object::Integer(4),
+        ),
+        (
+            vec![Object::Array(vec![Object::Integer(1), Object::Null])],
+            Object::Integer(2),
+        ),
+        (
+            vec![Object::Integer(1)],
+            Object::Error(String::from("argument to `len` not supported, got INTEGER")),
+        ),
+        (
+            vec![],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 0")),
+        ),
+        (
+            vec![Object::Array(vec![]), Object::Array(vec![])],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+    ];
+
+    for (args, expected) in tests {
+        assert_eq!(builtin_len(args, 0), expected);
+    }
+}
+
+#[test]
+fn builtin_array_functions_test() {
+    let array = || {
+        Object::Array(vec![
+            Object::Integer(1),
+            Object::Integer(2),
+            Object::Integer(3),
+        ])
+    };
+    let tests: [(crate::object::BuiltinFunction, Vec<Object>, Object); 14] = [
+        (builtin_first, vec![array()], Object::Integer(1)),
+        (builtin_first, vec![Object::Array(vec![])], Object::Null),
+        (
+            builtin_first,
+            vec![Object::Integer(1)],
+            Object::Error(String::from(
+                "argument to `first` must be ARRAY, got INTEGER",
+            )),
+        ),
+        (builtin_last, vec![array()], Object::Integer(3)),
+        (builtin_last, vec![Object::Array(vec![])], Object::Null),
+        (
+            builtin_last,
+            vec![array(), array()],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+        (
+            builtin_rest,
+            vec![array()],
+            Object::Array(vec![Object::Integer(2), Object::Integer(3)]),
+        ),
+        (
+            builtin_rest,
+            vec![Object::Array(vec![Object::Integer(1)])],
+            Object::Array(vec![]),
+        ),
+        (builtin_rest, vec![Object::Array(vec![])], Object::Null),
+        (
+            builtin_rest,
+            vec![Object::String(String::from("abc"))],
+            Object::Error(String::from("argument to `rest` must be ARRAY, got STRING")),
+        ),
+        (
+            builtin_push,
+            vec![Object::Array(vec![Object::Integer(1)]), Object::Integer(2)],
+            Object::Array(vec![Object::Integer(1), Object::Integer(2)]),
+        ),
+        (
+            builtin_push,
+            vec![Object::Array(vec![]), Object::Null],
+            Object::Array(vec![Object::Null]),
+        ),
+        (
+            builtin_push,
+            vec![Object::Integer(1), Object::Integer(2)],
+            Object::Error(String::from(
+                "argument to `push` must be ARRAY, got INTEGER",
+            )),
+        ),
+        (
+            builtin_push,
+            vec![array()],
+            Object::Error(String::from("wrong number of arguments: expected 2, got 1")),
+        ),
+    ];
+
+    for (function, args, expected) in tests {
+        assert_eq!(function(args, 0), expected);
+    }
+}
+
+#[test]
+fn builtin_type_test() {
+    let tests = [
+        (
+            vec![Object::Integer(1)],
+            Object::String(String::from("INTEGER")),
+        ),
+        (vec![Object::Null], Object::String(String::from("NULL"))),
+        (
+            vec![Object::Integer(1), Object::Integer(2)],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+        (
+            vec![],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 0")),
+        ),
+    ];
+
+    for (args, expected) in tests {
+        assert_eq!(builtin_type(args, 0), expected);
+    }
+}
+
+#[test]
+fn builtin_int_test() {
+    let tests = [
+        (
+            vec![Object::String(String::from("42"))],
+            Object::Integer(42),
+        ),
+        (
+            vec![Object::String(String::from("-7"))],
+            Object::Integer(-7),
+        ),
+        (vec![Object::Boolean(true)], Object::Integer(1)),
+        (vec![Object::Boolean(false)], Object::Integer(0)),
+        (vec![Object::Integer(42)], Object::Integer(42)),
+        (
+            vec![Object::String(String::from("4two"))],
+            Object::Error(String::from("cannot convert \"4two\" to INTEGER")),
+        ),
+        (
+            vec![Object::String(String::from("99999999999999999999"))],
+            Object::Error(String::from(
+                "cannot convert \"99999999999999999999\" to INTEGER",
+            )),
+        ),
+        (
+            vec![Object::Null],
+            Object::Error(String::from("argument to `int` not supported, got NULL")),
+        ),
+        (
+            vec![],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 0")),
+        ),
+    ];
+
+    for (args, expected) in tests {
+        assert_eq!(builtin_int(args, 0), expected);
+    }
+}
+
+#[test]
+fn builtin_str_test() {
+    let tests = [
+        (
+            vec![Object::Integer(42)],
+            Object::String(String::from("42")),
+        ),
+        (
+            vec![Object::Integer(-1)],
+            Object::String(String::from("-1")),
+        ),
+        (
+            vec![Object::Boolean(true)],
+            Object::String(String::from("true")),
+        ),
+        (
+            vec![Object::Boolean(false)],
+            Object::String(String::from("false")),
+        ),
+        (
+            vec![Object::String(String::from("hello"))],
+            Object::String(String::from("hello")),
+        ),
+        (
+            vec![Object::Array(vec![])],
+            Object::Error(String::from("argument to `str` not supported, got ARRAY")),
+        ),
+        (
+            vec![Object::Integer(1), Object::Integer(2)],
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+    ];
+
+    for (args, expected) in tests {
+        assert_eq!(builtin_str(args, 0), expected);
+    }
+}
+
+#[test]
+fn builtin_puts_test() {
+    let mut out = vec![];
+    let args = [
+        Object::String(String::from("hello")),
+        Object::String(String::from("world")),
+    ];
+    assert_eq!(puts(&mut out, &args), Object::Null);
+    assert_eq!(String::from_utf8(out).unwrap(), "hello\nworld\n");
+
+    let mut out = vec![];
+    assert_eq!(puts(&mut out, &[]), Object::Null);
+    assert!(out.is_empty());
+
+    let mut out = vec![];
+    puts(
+        &mut out,
+        &[Object::Integer(1), Object::Array(vec![Object::Null])],
+    );
+    assert_eq!(String::from_utf8(out).unwrap(), "1\n[null]\n");
+}
@@ -0,0 +1,39 @@
+//! Wraps text in ANSI escape codes so terminals show it in color.
+
+use std::io::{stdout, IsTerminal};
+
+const RESET: &str = "\x1b[0m";
+
+/// Whether stdout is a terminal, anywhere else the codes would show up as they are.
+pub fn enabled() -> bool {
+    stdout().is_terminal()
+}
+
+fn paint(code: u8, s: &str) -> String {
+    format!("\x1b[{}m{}{}", code, s, RESET)
+}
+
+pub fn red(s: &str) -> String {
+    paint(31, s)
+}
+
+pub fn green(s: &str) -> String {
+    paint(32, s)
+}
+
+pub fn yellow(s: &str) -> String {
+    paint(33, s)
+}
+
+pub fn cyan(s: &str) -> String {
+    paint(36, s)
+}
+
+#[test]
+fn color_test() {
+    assert_eq!(red("ERROR"), "\x1b[31mERROR\x1b[0m");
+    assert_eq!(green(">> "), "\x1b[32m>> \x1b[0m");
+    assert_eq!(yellow("42"), "\x1b[33m42\x1b[0m");
+    assert_eq!(cyan("hi"), "\x1b[36mhi\x1b[0m");
+    assert_eq!(red(""), "\x1b[31m\x1b[0m");
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::object::Object;
+
+/// The variables bound while evaluating a program. A function call's environment encloses the
+/// one the function was defined in, so names it doesn't bind itself are looked up there.
+#[derive(Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self {
+            store: HashMap::new(),
+            outer: None,
+        }
+    }
+
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            store: HashMap::new(),
+            outer: Some(outer),
+        }
+    }
+
+    /// Looks `name` up here and then in the enclosing environments.
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.outer.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Binds `name` to `val` in this environment, replacing whatever it was bound to before.
+    /// Enclosing environments are never changed, a binding there is shadowed instead.
+    pub fn set(&mut self, name: String, val: Object) {
+        self.store.insert(name, val);
+    }
+
+    /// Rebinds `name` where it's bound, here or in an enclosing environment. Returns whether
+    /// `name` was bound anywhere; if it wasn't, nothing is bound.
+    pub fn assign(&mut self, name: &str, val: Object) -> bool {
+        match self.store.get_mut(name) {
+            Some(value) => {
+                *value = val;
+                true
+            }
+            None => match &self.outer {
+                Some(outer) => outer.borrow_mut().assign(name, val),
+                None => false,
+            },
+        }
+    }
+
+    /// Whether `name` is bound in this environment itself, leaving the enclosing ones out.
+    pub fn binds(&self, name: &str) -> bool {
+        self.store.contains_key(name)
+    }
+}
+
+/// Functions hold the environment they're defined in, which usually binds the function itself,
+/// so only the names are shown to avoid going around in circles.
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Environment")
+            .field("names", &self.store.keys())
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+/// Environments are mutable, so two are only equal if they're the same one.
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+#[test]
+fn get_set_test() {
+    let mut env = Environment::new();
+    assert_eq!(env.get("x"), None);
+
+    env.set(String::from("x"), Object::Integer(5));
+    assert_eq!(env.get("x"), Some(Object::Integer(5)));
+
+    env.set(String::from("x"), Object::Boolean(true));
+    assert_eq!(env.get("x"), Some(Object::Boolean(true)));
+}
+
+#[test]
+fn enclosed_environment_test() {
+    let outer = Rc::new(RefCell::new(Environment::new()));
+    outer
+        .borrow_mut()
+        .set(String::from("x"), Object::Integer(1));
+    outer
+        .borrow_mut()
+        .set(String::from("y"), Object::Integer(2));
+
+    let mut inner = Environment::new_enclosed(Rc::clone(&outer));
+    assert_eq!(inner.get("x"), Some(Object::Integer(1)));
+
+    inner.set(String::from("x"), Object::Integer(10));
+    inner.set(String::from("z"), Object::Integer(3));
+    assert_eq!(inner.get("x"), Some(Object::Integer(10)));
+    assert_eq!(inner.get("y"), Some(Object::Integer(2)));
+    assert_eq!(inner.get("z"), Some(Object::Integer(3)));
+
+    assert_eq!(outer.borrow().get("x"), Some(Object::Integer(1)));
+    assert_eq!(outer.borrow().get("z"), None);
+
+    assert!(inner.binds("x"));
+    assert!(!inner.binds("w"));
+    assert!(!inner.binds("y"));
+}
+
+#[test]
+fn assign_test() {
+    let outer = Rc::new(RefCell::new(Environment::new()));
+    outer
+        .borrow_mut()
+        .set(String::from("x"), Object::Integer(1));
+    let mut inner = Environment::new_enclosed(Rc::clone(&outer));
+    inner.set(String::from("y"), Object::Integer(2));
+
+    assert!(inner.assign("x", Object::Integer(10)));
+    assert!(inner.assign("y", Object::Integer(20)));
+    assert!(!inner.assign("z", Object::Integer(30)));
+
+    assert_eq!(outer.borrow().get("x"), Some(Object::Integer(10)));
+    assert_eq!(inner.get("y"), Some(Object::Integer(20)));
+    assert_eq!(outer.borrow().get("y"), None);
+    assert_eq!(inner.get("z"), None);
+}
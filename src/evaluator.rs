@@ -0,0 +1,1540 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{Expression, Program, Spanned, Statement};
+use crate::builtins;
+use crate::environment::Environment;
+use crate::lexer::Token;
+use crate::object::{HashPair, Hashable, Object};
+
+const TRUE: Object = Object::Boolean(true);
+const FALSE: Object = Object::Boolean(false);
+const NULL: Object = Object::Null;
+
+/// How many function calls can be in progress at once by default. Each one recurses through the
+/// evaluator, so without a limit a runaway program would overflow the stack. That many calls
+/// take around 5 to 10MiB of stack in a debug build, depending on how deep in an expression each
+/// one is, so programs need to be evaluated on a thread with a stack to match.
+pub const MAX_CALL_DEPTH: usize = 512;
+
+/// `env` is left as the scope the program ended in, which is a new one enclosing it if the
+/// program bound a name again, so another program run in it sees the bindings it left.
+pub fn eval_program(program: &Program, env: &mut Rc<RefCell<Environment>>) -> Object {
+    eval_program_with_max_depth(program, env, MAX_CALL_DEPTH)
+}
+
+/// `eval_program` allowing up to `max_depth` function calls in progress at once, for stacks
+/// smaller or bigger than the one `MAX_CALL_DEPTH` assumes.
+pub fn eval_program_with_max_depth(
+    program: &Program,
+    env: &mut Rc<RefCell<Environment>>,
+    max_depth: usize,
+) -> Object {
+    unwrap_return_value(eval_statements(&program.statements, env, max_depth))
+}
+
+pub fn eval(node: &Statement, env: &Rc<RefCell<Environment>>) -> Object {
+    eval_statement(node, env, MAX_CALL_DEPTH)
+}
+
+/// The functions below take `calls_left`, how many more function calls can be in progress, to
+/// hand on to `apply_function`.
+fn eval_statement(node: &Statement, env: &Rc<RefCell<Environment>>, calls_left: usize) -> Object {
+    match node {
+        Statement::Let(
+            Spanned {
+                node: Expression::Identifier(Token::Identifier(name)),
+                ..
+            },
+            value,
+        ) => {
+            let value = eval_expression(value, env, calls_left);
+            if is_error(&value) {
+                return value;
+            }
+            env.borrow_mut().set(name.to_string(), value);
+            NULL
+        }
+        Statement::Return(None) => Object::ReturnValue(Box::new(NULL)),
+        Statement::Return(Some(value)) => {
+            let value = eval_expression(value, env, calls_left);
+            if is_error(&value) {
+                return value;
+            }
+            Object::ReturnValue(Box::new(value))
+        }
+        Statement::Expression(e) => eval_expression(e, env, calls_left),
+        Statement::Block(statements) => eval_block_statement(statements, env, calls_left),
+        s => Object::Error(format!("can't evaluate `{}` yet", s)),
+    }
+}
+
+fn eval_block_statement(
+    statements: &[Spanned<Statement>],
+    env: &Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Object {
+    eval_statements(statements, &mut Rc::clone(env), calls_left)
+}
+
+/// Evaluates the statements in order, `env` ending up as the scope the last one ran in.
+fn eval_statements(
+    statements: &[Spanned<Statement>],
+    env: &mut Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Object {
+    let mut result = NULL;
+    for s in statements {
+        enter_let_scope(s, env);
+        result = eval_statement(s, env, calls_left);
+        // left as is so the return keeps leaving the enclosing blocks too
+        if let Object::ReturnValue(_) | Object::Error(_) = result {
+            return result;
+        }
+    }
+
+    result
+}
+
+/// A `let` of a name that's already bound in `env` itself starts a new scope enclosed by it for
+/// the rest of the block, so functions defined in `env` keep seeing the binding they had.
+/// Other names are bound in `env`, where the functions defined before them see them too.
+fn enter_let_scope(statement: &Statement, env: &mut Rc<RefCell<Environment>>) {
+    if let Statement::Let(
+        Spanned {
+            node: Expression::Identifier(Token::Identifier(name)),
+            ..
+        },
+        _,
+    ) = statement
+    {
+        if env.borrow().binds(name) {
+            *env = Rc::new(RefCell::new(Environment::new_enclosed(Rc::clone(env))));
+        }
+    }
+}
+
+fn unwrap_return_value(value: Object) -> Object {
+    match value {
+        Object::ReturnValue(value) => *value,
+        value => value,
+    }
+}
+
+fn eval_expression(node: &Expression, env: &Rc<RefCell<Environment>>, calls_left: usize) -> Object {
+    match node {
+        Expression::Identifier(Token::Identifier(name)) => {
+            match builtins::get(name).or_else(|| env.borrow().get(name)) {
+                Some(value) => value,
+                None => Object::Error(format!("identifier not found: {}", name)),
+            }
+        }
+        Expression::IntegerLiteral { value, .. } => Object::Integer(*value),
+        Expression::Boolean(b) => native_bool_to_boolean(*b),
+        Expression::Null => NULL,
+        Expression::StringLiteral(Token::StringLiteral(s)) => Object::String(s.clone()),
+        Expression::ArrayLiteral(elements) => match eval_expressions(elements, env, calls_left) {
+            Ok(elements) => Object::Array(elements),
+            Err(error) => error,
+        },
+        Expression::HashLiteral(pairs) => eval_hash_literal(pairs, env, calls_left),
+        Expression::Index { left, index } => {
+            let left = eval_expression(left, env, calls_left);
+            if is_error(&left) {
+                return left;
+            }
+            let index = eval_expression(index, env, calls_left);
+            if is_error(&index) {
+                return index;
+            }
+            eval_index_expression(left, index)
+        }
+        Expression::Prefix { operator, right } => {
+            let right = eval_expression(right, env, calls_left);
+            if is_error(&right) {
+                return right;
+            }
+            eval_prefix_expression(operator, right)
+        }
+        Expression::Infix {
+            left,
+            operator,
+            right,
+        } => {
+            let left = eval_expression(left, env, calls_left);
+            if is_error(&left) {
+                return left;
+            }
+            let right = eval_expression(right, env, calls_left);
+            if is_error(&right) {
+                return right;
+            }
+            eval_infix_expression(operator, left, right)
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => eval_if_expression(
+            condition,
+            consequence,
+            alternative.as_deref().map(|a| &a.node),
+            env,
+            calls_left,
+        ),
+        Expression::FunctionLiteral { parameters, body } => Object::Function {
+            parameters: parameters.clone(),
+            body: Rc::new(body.as_ref().clone()),
+            env: Rc::clone(env),
+        },
+        Expression::Assign { target, value } => {
+            let value = eval_expression(value, env, calls_left);
+            if is_error(&value) {
+                return value;
+            }
+            eval_assignment(target, value, env, calls_left)
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            let function = eval_expression(function, env, calls_left);
+            if is_error(&function) {
+                return function;
+            }
+            match eval_expressions(arguments, env, calls_left) {
+                Ok(arguments) => apply_function(function, arguments, calls_left),
+                Err(error) => error,
+            }
+        }
+        e => Object::Error(format!("can't evaluate `{}` yet", e)),
+    }
+}
+
+fn eval_prefix_expression(operator: &Token, right: Object) -> Object {
+    match (operator, right) {
+        (Token::Bang, right) => native_bool_to_boolean(!right.is_truthy()),
+        (Token::Minus, Object::Integer(n)) => checked(n.checked_neg()),
+        (operator, right) => Object::Error(format!(
+            "unknown operator: {}{}",
+            operator,
+            right.type_name()
+        )),
+    }
+}
+
+fn eval_infix_expression(operator: &Token, left: Object, right: Object) -> Object {
+    match (left, right) {
+        (Object::Integer(l), Object::Integer(r)) => eval_integer_infix_expression(operator, l, r),
+        (Object::Boolean(l), Object::Boolean(r)) => match operator {
+            Token::EQ => native_bool_to_boolean(l == r),
+            Token::NotEq => native_bool_to_boolean(l != r),
+            _ => Object::Error(format!("unknown operator: BOOLEAN {} BOOLEAN", operator)),
+        },
+        // anything can be compared with null to check if it's there
+        (left, right)
+            if matches!(operator, Token::EQ | Token::NotEq) && (left == NULL || right == NULL) =>
+        {
+            native_bool_to_boolean((left == right) == (*operator == Token::EQ))
+        }
+        (Object::String(l), Object::String(r)) if *operator == Token::Plus => {
+            Object::String(l + &r)
+        }
+        (left @ Object::String(_), right) | (left, right @ Object::String(_)) => {
+            Object::Error(format!(
+                "unsupported operator: {} {} {}",
+                left.type_name(),
+                operator,
+                right.type_name()
+            ))
+        }
+        (left, right) if left.type_name() != right.type_name() => Object::Error(format!(
+            "type mismatch: {} {} {}",
+            left.type_name(),
+            operator,
+            right.type_name()
+        )),
+        (left, right) => Object::Error(format!(
+            "unknown operator: {} {} {}",
+            left.type_name(),
+            operator,
+            right.type_name()
+        )),
+    }
+}
+
+fn eval_integer_infix_expression(operator: &Token, left: i64, right: i64) -> Object {
+    match operator {
+        Token::Plus => checked(left.checked_add(right)),
+        Token::Minus => checked(left.checked_sub(right)),
+        Token::Asterisk => checked(left.checked_mul(right)),
+        Token::Slash if right == 0 => Object::Error(String::from("division by zero")),
+        // only `i64::MIN / -1` is left to overflow
+        Token::Slash => checked(left.checked_div(right)),
+        Token::LT => native_bool_to_boolean(left < right),
+        Token::GT => native_bool_to_boolean(left > right),
+        Token::EQ => native_bool_to_boolean(left == right),
+        Token::NotEq => native_bool_to_boolean(left != right),
+        _ => Object::Error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    }
+}
+
+/// Rebinds an already bound name where it's bound, evaluating to the new value so assignments
+/// can be chained. Arrays and hashes are values, so assigning to an element of one rebinds the
+/// name it's reached from to a copy with the element replaced.
+fn eval_assignment(
+    target: &Expression,
+    value: Object,
+    env: &Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Object {
+    match target {
+        Expression::Identifier(Token::Identifier(name)) => {
+            if env.borrow_mut().assign(name, value.clone()) {
+                value
+            } else {
+                Object::Error(format!("identifier not found: {}", name))
+            }
+        }
+        Expression::Index { left, index } => {
+            let container = eval_expression(left, env, calls_left);
+            if is_error(&container) {
+                return container;
+            }
+            let index = eval_expression(index, env, calls_left);
+            if is_error(&index) {
+                return index;
+            }
+            let container = match eval_index_assignment(container, index, value.clone()) {
+                Ok(container) => container,
+                Err(error) => return error,
+            };
+            match eval_assignment(left, container, env, calls_left) {
+                error @ Object::Error(_) => error,
+                _ => value,
+            }
+        }
+        target => Object::Error(format!("can't assign to `{}`", target)),
+    }
+}
+
+/// A copy of `left` with the element at `index` replaced by `value`. Arrays can only have their
+/// existing elements replaced, hashes get the key added if it's missing.
+fn eval_index_assignment(left: Object, index: Object, value: Object) -> Result<Object, Object> {
+    match (left, index) {
+        (Object::Array(mut elements), Object::Integer(i)) => {
+            match usize::try_from(i).ok().and_then(|i| elements.get_mut(i)) {
+                Some(element) => *element = value,
+                None => return Err(Object::Error(format!("index out of range: {}", i))),
+            }
+            Ok(Object::Array(elements))
+        }
+        (Object::Hash(mut pairs), index) => match index.hash_key() {
+            Some(key) => {
+                pairs.insert(key, HashPair { key: index, value });
+                Ok(Object::Hash(pairs))
+            }
+            None => Err(Object::Error(format!(
+                "unusable as hash key: {}",
+                index.type_name()
+            ))),
+        },
+        (left, index) => Err(Object::Error(format!(
+            "index operator not supported: {}[{}]",
+            left.type_name(),
+            index.type_name()
+        ))),
+    }
+}
+
+/// The result of checked integer arithmetic, `None` meaning it overflowed.
+fn checked(result: Option<i64>) -> Object {
+    match result {
+        Some(n) => Object::Integer(n),
+        None => Object::Error(String::from("integer overflow")),
+    }
+}
+
+fn eval_hash_literal(
+    pairs: &[(Spanned<Expression>, Spanned<Expression>)],
+    env: &Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Object {
+    let mut hash = HashMap::new();
+    for (key, value) in pairs {
+        let key = eval_expression(key, env, calls_left);
+        if is_error(&key) {
+            return key;
+        }
+        let hash_key = match key.hash_key() {
+            Some(hash_key) => hash_key,
+            None => return Object::Error(format!("unusable as hash key: {}", key.type_name())),
+        };
+        let value = eval_expression(value, env, calls_left);
+        if is_error(&value) {
+            return value;
+        }
+        hash.insert(hash_key, HashPair { key, value });
+    }
+
+    Object::Hash(hash)
+}
+
+/// Indexes outside of the array, negative ones included, and keys missing from the hash give
+/// `null`.
+fn eval_index_expression(left: Object, index: Object) -> Object {
+    match (left, index) {
+        (Object::Array(elements), Object::Integer(i)) => usize::try_from(i)
+            .ok()
+            .and_then(|i| elements.get(i).cloned())
+            .unwrap_or(NULL),
+        (Object::Hash(pairs), index) => match index.hash_key() {
+            Some(key) => pairs
+                .get(&key)
+                .map(|pair| pair.value.clone())
+                .unwrap_or(NULL),
+            None => Object::Error(format!("unusable as hash key: {}", index.type_name())),
+        },
+        (left, index) => Object::Error(format!(
+            "index operator not supported: {}[{}]",
+            left.type_name(),
+            index.type_name()
+        )),
+    }
+}
+
+fn eval_if_expression(
+    condition: &Expression,
+    consequence: &Statement,
+    alternative: Option<&Statement>,
+    env: &Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Object {
+    let condition = eval_expression(condition, env, calls_left);
+    if is_error(&condition) {
+        return condition;
+    }
+    if condition.is_truthy() {
+        eval_statement(consequence, env, calls_left)
+    } else {
+        match alternative {
+            Some(alternative) => eval_statement(alternative, env, calls_left),
+            None => NULL,
+        }
+    }
+}
+
+/// Evaluates the expressions in order, stopping at the first error.
+fn eval_expressions(
+    expressions: &[Spanned<Expression>],
+    env: &Rc<RefCell<Environment>>,
+    calls_left: usize,
+) -> Result<Vec<Object>, Object> {
+    let mut values = vec![];
+    for e in expressions {
+        let value = eval_expression(e, env, calls_left);
+        if is_error(&value) {
+            return Err(value);
+        }
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// Evaluates the function's body in a new environment binding its parameters, enclosed by the
+/// one the function was defined in. Builtins are just called with the arguments. `calls_left` is how
+/// many more calls can be in progress, this one included.
+///
+/// Calls the function makes of itself in tail position don't recurse, the body is evaluated
+/// again with the new arguments instead, so those can go on past the limit. See
+/// `eval_tail` for what counts as one.
+pub fn apply_function(function: Object, mut arguments: Vec<Object>, calls_left: usize) -> Object {
+    let (parameters, body, env) = match &function {
+        Object::Function {
+            parameters,
+            body,
+            env,
+        } => (parameters, body, env),
+        Object::Builtin(builtin) => return (builtin.function)(arguments, calls_left),
+        f => return Object::Error(format!("not a function: {}", f.type_name())),
+    };
+
+    if calls_left == 0 {
+        return Object::Error(String::from("recursion limit exceeded"));
+    }
+
+    loop {
+        if parameters.len() != arguments.len() {
+            break Object::Error(format!(
+                "wrong number of arguments: expected {}, got {}",
+                parameters.len(),
+                arguments.len()
+            ));
+        }
+
+        let mut call_env = Environment::new_enclosed(Rc::clone(env));
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            call_env.set(parameter.clone(), argument);
+        }
+        match eval_tail(
+            body,
+            &Rc::new(RefCell::new(call_env)),
+            &function,
+            calls_left - 1,
+        ) {
+            Tail::Value(value) => break unwrap_return_value(value),
+            Tail::SelfCall(next) => arguments = next,
+        }
+    }
+}
+
+/// What evaluating a function body in tail position gave.
+enum Tail {
+    Value(Object),
+    /// The function called itself as the last thing it did, with these arguments.
+    SelfCall(Vec<Object>),
+}
+
+/// Evaluates the body of `function`, stopping short of a call to `function` itself in tail
+/// position. Those are calls that are:
+///
+/// - the value of a `return`, anywhere in the body
+/// - the last expression statement of the body
+/// - in tail position of a block of an `if` (or `else if`) in tail position
+///
+/// and whose callee evaluates to `function`, which is usually the name it's bound to. A call
+/// inside another expression, like `n * f(n - 1)`, isn't in tail position.
+fn eval_tail(
+    statement: &Statement,
+    env: &Rc<RefCell<Environment>>,
+    function: &Object,
+    calls_left: usize,
+) -> Tail {
+    match statement {
+        Statement::Block(statements) => {
+            let mut env = Rc::clone(env);
+            for (i, s) in statements.iter().enumerate() {
+                match &s.node {
+                    Statement::Return(Some(e)) => {
+                        return eval_tail_expression(e, &env, function, calls_left)
+                    }
+                    Statement::Expression(e) if i + 1 == statements.len() => {
+                        return eval_tail_expression(e, &env, function, calls_left)
+                    }
+                    s => {
+                        enter_let_scope(s, &mut env);
+                        let result = eval_statement(s, &env, calls_left);
+                        if let Object::ReturnValue(_) | Object::Error(_) = result {
+                            return Tail::Value(result);
+                        }
+                    }
+                }
+            }
+            Tail::Value(NULL)
+        }
+        Statement::Expression(e) => eval_tail_expression(e, env, function, calls_left),
+        s => Tail::Value(eval_statement(s, env, calls_left)),
+    }
+}
+
+fn eval_tail_expression(
+    expression: &Expression,
+    env: &Rc<RefCell<Environment>>,
+    function: &Object,
+    calls_left: usize,
+) -> Tail {
+    match expression {
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            let condition = eval_expression(condition, env, calls_left);
+            if is_error(&condition) {
+                return Tail::Value(condition);
+            }
+            if condition.is_truthy() {
+                eval_tail(consequence, env, function, calls_left)
+            } else {
+                match alternative {
+                    Some(alternative) => eval_tail(alternative, env, function, calls_left),
+                    None => Tail::Value(NULL),
+                }
+            }
+        }
+        Expression::Call {
+            function: callee,
+            arguments,
+        } => {
+            let callee = eval_expression(callee, env, calls_left);
+            if is_error(&callee) {
+                return Tail::Value(callee);
+            }
+            match eval_expressions(arguments, env, calls_left) {
+                Ok(arguments) if is_same_function(&callee, function) => Tail::SelfCall(arguments),
+                Ok(arguments) => Tail::Value(apply_function(callee, arguments, calls_left)),
+                Err(error) => Tail::Value(error),
+            }
+        }
+        e => Tail::Value(eval_expression(e, env, calls_left)),
+    }
+}
+
+/// Whether both are copies of the same function value, rather than functions that just look
+/// alike, such as two made by the same literal in different calls.
+fn is_same_function(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (
+            Object::Function { body, env, .. },
+            Object::Function {
+                body: other_body,
+                env: other_env,
+                ..
+            },
+        ) => Rc::ptr_eq(body, other_body) && Rc::ptr_eq(env, other_env),
+        _ => false,
+    }
+}
+
+fn is_error(obj: &Object) -> bool {
+    matches!(obj, Object::Error(_))
+}
+
+fn native_bool_to_boolean(b: bool) -> Object {
+    if b {
+        TRUE
+    } else {
+        FALSE
+    }
+}
+
+#[cfg(test)]
+fn test_eval(input: &str) -> Object {
+    use crate::parser::Parser;
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    eval_program(&prog, &mut Rc::new(RefCell::new(Environment::new())))
+}
+
+#[test]
+fn eval_integer_expression_test() {
+    let tests = [("5;", 5), ("10", 10), ("9223372036854775807", i64::MAX)];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Integer(expected));
+    }
+}
+
+#[test]
+fn eval_boolean_expression_test() {
+    assert_eq!(test_eval("true;"), Object::Boolean(true));
+    assert_eq!(test_eval("false"), Object::Boolean(false));
+}
+
+#[test]
+fn eval_bang_operator_test() {
+    let tests = [
+        ("!true", false),
+        ("!false", true),
+        ("!5", false),
+        ("!!true", true),
+        ("!!5", true),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Boolean(expected), "{}", input);
+    }
+    assert_eq!(
+        eval_prefix_expression(&Token::Bang, Object::Null),
+        Object::Boolean(true)
+    );
+}
+
+#[test]
+fn eval_minus_operator_test() {
+    assert_eq!(test_eval("-5"), Object::Integer(-5));
+    assert_eq!(test_eval("--5"), Object::Integer(5));
+    assert_eq!(
+        test_eval("-true"),
+        Object::Error(String::from("unknown operator: -BOOLEAN"))
+    );
+}
+
+#[test]
+fn eval_integer_infix_expression_test() {
+    let tests = [
+        ("5 + 5 + 5 + 5 - 10", 10),
+        ("2 * 2 * 2 * 2 * 2", 32),
+        ("-50 + 100 + -50", 0),
+        ("5 * 2 + 10", 20),
+        ("5 + 2 * 10", 25),
+        ("20 + 2 * -10", 0),
+        ("50 / 2 * 2 + 10", 60),
+        ("2 * (5 + 10)", 30),
+        ("3 * (3 * 3) + 10", 37),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ("7 / 2", 3),
+        ("-7 / 2", -3),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{}", input);
+    }
+}
+
+#[test]
+fn eval_integer_overflow_test() {
+    let overflow = || Object::Error(String::from("integer overflow"));
+    let tests = [
+        ("9223372036854775807 + 1", overflow()),
+        ("9223372036854775806 + 1", Object::Integer(i64::MAX)),
+        ("-9223372036854775807 + -2", overflow()),
+        ("-9223372036854775807 - 1", Object::Integer(i64::MIN)),
+        ("-9223372036854775807 - 2", overflow()),
+        ("9223372036854775807 - -1", overflow()),
+        ("4611686018427387904 * 2", overflow()),
+        ("4611686018427387903 * 2", Object::Integer(i64::MAX - 1)),
+        ("-4611686018427387904 * 2", Object::Integer(i64::MIN)),
+        ("-4611686018427387905 * 2", overflow()),
+        ("(-9223372036854775807 - 1) / -1", overflow()),
+        ("(-9223372036854775807 - 1) / 1", Object::Integer(i64::MIN)),
+        ("-(-9223372036854775807 - 1)", overflow()),
+        ("-(-9223372036854775807)", Object::Integer(i64::MAX)),
+        ("let x = 9223372036854775807 + 1; 5", overflow()),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_comparison_test() {
+    let tests = [
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 < 1", false),
+        ("1 == 1", true),
+        ("1 != 1", false),
+        ("1 == 2", false),
+        ("1 != 2", true),
+        ("true == true", true),
+        ("false == false", true),
+        ("true == false", false),
+        ("true != false", true),
+        ("(1 < 2) == true", true),
+        ("(1 > 2) == true", false),
+        ("!(1 > 2) != false", true),
+        ("null == null", true),
+        ("null != null", false),
+        ("1 == null", false),
+        ("null != 1", true),
+        ("[1][5] == null", true),
+        ("let x = null; x == null", true),
+        ("if (false) { 1 } == null", true),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Boolean(expected), "{}", input);
+    }
+}
+
+#[test]
+fn eval_infix_errors_test() {
+    let tests = [
+        ("5 / 0", "division by zero"),
+        ("5 / (2 - 2)", "division by zero"),
+        ("5 + true", "type mismatch: INTEGER + BOOLEAN"),
+        ("false == 0", "type mismatch: BOOLEAN == INTEGER"),
+        ("true + false", "unknown operator: BOOLEAN + BOOLEAN"),
+        ("true < false", "unknown operator: BOOLEAN < BOOLEAN"),
+        ("null + null", "unknown operator: NULL + NULL"),
+        ("null < 1", "type mismatch: NULL < INTEGER"),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(
+            test_eval(input),
+            Object::Error(String::from(expected)),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn eval_if_expression_test() {
+    let tests = [
+        ("if (true) { 10 }", Object::Integer(10)),
+        ("if (false) { 10 }", Object::Null),
+        ("if (1) { 10 }", Object::Integer(10)),
+        ("if (1 < 2) { 10 }", Object::Integer(10)),
+        ("if (1 > 2) { 10 }", Object::Null),
+        ("if (false) { 10 } else { 20 }", Object::Integer(20)),
+        ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10)),
+        ("if (true) {}", Object::Null),
+        (
+            "if (false) { 1 } else if (true) { 2 } else { 3 }",
+            Object::Integer(2),
+        ),
+        (
+            "if (false) { 1 } else if (false) { 2 } else { 3 }",
+            Object::Integer(3),
+        ),
+        ("if (false) { 1 } else if (false) { 2 }", Object::Null),
+        (
+            "let f = fn(x) { if (x < 0) { return -1 } else if (x == 0) { return 0 } 1 }; [f(-5), f(0), f(5)]",
+            Object::Array(vec![
+                Object::Integer(-1),
+                Object::Integer(0),
+                Object::Integer(1),
+            ]),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_let_statement_test() {
+    let tests = [
+        ("let x = 5; x;", Object::Integer(5)),
+        ("let a = 5 * 5; a;", Object::Integer(25)),
+        ("let a = 5; let b = a; b;", Object::Integer(5)),
+        (
+            "let a = 5; let b = a; let c = a + b + 5; c;",
+            Object::Integer(15),
+        ),
+        ("let a = 1; let a = a + 1; a;", Object::Integer(2)),
+        ("let a = 5;", Object::Null),
+        (
+            "foobar",
+            Object::Error(String::from("identifier not found: foobar")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_function_test() {
+    let tests = [
+        ("let f = fn(x) { x * 2 }; f(5);", Object::Integer(10)),
+        (
+            "let identity = fn(x) { x; }; identity(5);",
+            Object::Integer(5),
+        ),
+        (
+            "let add = fn(x, y) { x + y; }; add(5, 5);",
+            Object::Integer(10),
+        ),
+        (
+            "let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));",
+            Object::Integer(20),
+        ),
+        ("fn(x) { x; }(5)", Object::Integer(5)),
+        ("let x = 1; let f = fn() { x }; f();", Object::Integer(1)),
+        (
+            "let x = 1; let f = fn(x) { x }; f(2); x;",
+            Object::Integer(1),
+        ),
+        (
+            "let newAdder = fn(x) { fn(y) { x + y } }; let addTwo = newAdder(2); addTwo(3);",
+            Object::Integer(5),
+        ),
+        (
+            "let f = fn(x, y) { x }; f(1);",
+            Object::Error(String::from("wrong number of arguments: expected 2, got 1")),
+        ),
+        (
+            "5(1)",
+            Object::Error(String::from("not a function: INTEGER")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_return_statement_test() {
+    let tests = [
+        ("return 10;", 10),
+        ("return 5; 9;", 5),
+        ("return 2 * 5; 9;", 10),
+        ("9; return 2 * 5; 9;", 10),
+        ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+        ("if (true) { return 1; } 2;", 1),
+        ("let f = fn(x) { return x; x + 10; }; f(10);", 10),
+        (
+            "let f = fn(x) { if (x > 5) { return 1; } 2 }; f(10) + f(0);",
+            3,
+        ),
+        ("let f = fn() { return 1; }; f(); 2;", 2),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{}", input);
+    }
+
+    assert_eq!(test_eval("return; 5"), Object::Null);
+    assert_eq!(test_eval("let f = fn() { return; 1 }; f()"), Object::Null);
+    assert_eq!(
+        test_eval("let f = fn() { return null; 1 }; f()"),
+        Object::Null
+    );
+}
+
+#[test]
+fn eval_error_propagation_test() {
+    let tests = [
+        ("5 + (5 + true)", "type mismatch: INTEGER + BOOLEAN"),
+        ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+        ("-(true + 1)", "type mismatch: BOOLEAN + INTEGER"),
+        ("!(true + 1)", "type mismatch: BOOLEAN + INTEGER"),
+        ("(1 / 0) + x", "division by zero"),
+        ("if (1 / 0) { 1 } else { 2 }", "division by zero"),
+        (
+            "if (10 > 1) { true + false; 10 }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+        ),
+        (
+            "if (10 > 1) { if (10 > 1) { return true + false; } return 1; }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+        ),
+        ("let x = -true; x", "unknown operator: -BOOLEAN"),
+        ("return -true; 1", "unknown operator: -BOOLEAN"),
+        ("let f = fn(x) { x }; f(1 / 0, y)", "division by zero"),
+        ("f(1 / 0)", "identifier not found: f"),
+        (
+            "let f = fn(x) { x + true }; f(1) + 2",
+            "type mismatch: INTEGER + BOOLEAN",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(
+            test_eval(input),
+            Object::Error(String::from(expected)),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn eval_string_test() {
+    let tests = [
+        ("\"hello\"", Object::String(String::from("hello"))),
+        (
+            "\"hello\" + \" \" + \"world\"",
+            Object::String(String::from("hello world")),
+        ),
+        ("let s = \"a\"; s + s", Object::String(String::from("aa"))),
+        (
+            "\"a\" - \"b\"",
+            Object::Error(String::from("unsupported operator: STRING - STRING")),
+        ),
+        (
+            "\"a\" + 5",
+            Object::Error(String::from("unsupported operator: STRING + INTEGER")),
+        ),
+        (
+            "5 + (5 + \"a\")",
+            Object::Error(String::from("unsupported operator: INTEGER + STRING")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_array_test() {
+    let tests = [
+        (
+            "[1, 2 * 2, 3 + 3]",
+            Object::Array(vec![
+                Object::Integer(1),
+                Object::Integer(4),
+                Object::Integer(6),
+            ]),
+        ),
+        ("[]", Object::Array(vec![])),
+        ("[1, 2, 3][1]", Object::Integer(2)),
+        ("[1, 1 + 1, 3][0]", Object::Integer(1)),
+        ("let i = 0; [1][i]", Object::Integer(1)),
+        ("[1, 2, 3][1 + 1]", Object::Integer(3)),
+        ("let a = [1, 2, 3]; a[0] + a[1] + a[2]", Object::Integer(6)),
+        ("[[1, 2], [3]][1][0]", Object::Integer(3)),
+        ("[1, 2, 3][10]", Object::Null),
+        ("[1, 2, 3][3]", Object::Null),
+        ("[1, 2, 3][-1]", Object::Null),
+        (
+            "[1, true + 1]",
+            Object::Error(String::from("type mismatch: BOOLEAN + INTEGER")),
+        ),
+        (
+            "1[0]",
+            Object::Error(String::from(
+                "index operator not supported: INTEGER[INTEGER]",
+            )),
+        ),
+        (
+            "[1][true]",
+            Object::Error(String::from("index operator not supported: ARRAY[BOOLEAN]")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_hash_literal_test() {
+    let input = "let two = \"two\";
+    {\"one\": 10 - 9, two: 1 + 1, \"thr\" + \"ee\": 6 / 2, 4: 4, true: 5, false: 6}";
+
+    let hash = match test_eval(input) {
+        Object::Hash(hash) => hash,
+        other => panic!("expected a hash, got {:?}", other),
+    };
+    let expected = [
+        (Object::String(String::from("one")), 1),
+        (Object::String(String::from("two")), 2),
+        (Object::String(String::from("three")), 3),
+        (Object::Integer(4), 4),
+        (Object::Boolean(true), 5),
+        (Object::Boolean(false), 6),
+    ];
+    assert_eq!(hash.len(), expected.len());
+    for (key, value) in expected {
+        let pair = &hash[&key.hash_key().unwrap()];
+        assert_eq!(pair.key, key);
+        assert_eq!(pair.value, Object::Integer(value));
+    }
+}
+
+#[test]
+fn eval_hash_index_test() {
+    let tests = [
+        ("{\"key\": 1}[\"key\"]", Object::Integer(1)),
+        ("{\"key\": 1}[\"other\"]", Object::Null),
+        ("let key = \"k\"; {\"k\": 5}[key]", Object::Integer(5)),
+        ("{}[\"key\"]", Object::Null),
+        ("{5: 5}[5]", Object::Integer(5)),
+        ("{true: 5}[true]", Object::Integer(5)),
+        ("{1: 5}[true]", Object::Null),
+        ("{\"a\": 1, \"a\": 2}[\"a\"]", Object::Integer(2)),
+        (
+            "{\"name\": \"Monkey\"}[fn(x) { x }]",
+            Object::Error(String::from("unusable as hash key: FUNCTION")),
+        ),
+        (
+            "{[1]: 2}",
+            Object::Error(String::from("unusable as hash key: ARRAY")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_len_test() {
+    let tests = [
+        ("len(\"hello\")", Object::Integer(5)),
+        ("len([1, 2, 3])", Object::Integer(3)),
+        ("let a = [1]; len(a) + len(\"\")", Object::Integer(1)),
+        ("let len = fn(x) { 0 }; len(\"four\")", Object::Integer(4)),
+        (
+            "len(1)",
+            Object::Error(String::from("argument to `len` not supported, got INTEGER")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_array_functions_test() {
+    let tests = [
+        ("first(rest([1, 2, 3]))", Object::Integer(2)),
+        ("last(push([1, 2], 3))", Object::Integer(3)),
+        (
+            "let a = [1, 2]; let b = push(rest(a), 3); a",
+            Object::Array(vec![Object::Integer(1), Object::Integer(2)]),
+        ),
+        (
+            "let a = [1, 2]; let b = push(rest(a), 3); b",
+            Object::Array(vec![Object::Integer(2), Object::Integer(3)]),
+        ),
+        ("first([])", Object::Null),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_type_test() {
+    let tests = [
+        ("type(1)", "INTEGER"),
+        ("type(\"hi\")", "STRING"),
+        ("type(true)", "BOOLEAN"),
+        ("type([])", "ARRAY"),
+        ("type({})", "HASH"),
+        ("type(fn() {})", "FUNCTION"),
+        ("type(type)", "BUILTIN"),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(
+            test_eval(input),
+            Object::String(String::from(expected)),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        test_eval("type()"),
+        Object::Error(String::from("wrong number of arguments: expected 1, got 0"))
+    );
+}
+
+#[test]
+fn eval_builtin_conversions_test() {
+    let tests = [
+        ("int(\"4\" + \"2\") + 1", Object::Integer(43)),
+        ("str(40 + 2) + \"!\"", Object::String(String::from("42!"))),
+        ("int(str(7)) == 7", Object::Boolean(true)),
+        ("int(1 > 0) + int(false)", Object::Integer(1)),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_map_test() {
+    let tests = [
+        (
+            "map([1, 2, 3], fn(x) { x * 2 })",
+            Object::Array(vec![
+                Object::Integer(2),
+                Object::Integer(4),
+                Object::Integer(6),
+            ]),
+        ),
+        (
+            "let square = fn(x) { x * x }; map([1, 2, 3], square)",
+            Object::Array(vec![
+                Object::Integer(1),
+                Object::Integer(4),
+                Object::Integer(9),
+            ]),
+        ),
+        ("map([], fn(x) { x })", Object::Array(vec![])),
+        (
+            "map([\"a\", \"bc\"], len)",
+            Object::Array(vec![Object::Integer(1), Object::Integer(2)]),
+        ),
+        (
+            "map([1, 2], fn(x) { x + true })",
+            Object::Error(String::from("type mismatch: INTEGER + BOOLEAN")),
+        ),
+        (
+            "map(1, fn(x) { x })",
+            Object::Error(String::from("argument to `map` must be ARRAY, got INTEGER")),
+        ),
+        (
+            "map([1], 2)",
+            Object::Error(String::from(
+                "argument to `map` must be FUNCTION, got INTEGER",
+            )),
+        ),
+        (
+            "map([1])",
+            Object::Error(String::from("wrong number of arguments: expected 2, got 1")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_filter_test() {
+    let tests = [
+        (
+            "filter([1, 2, 3, 4], fn(x) { x > 2 })",
+            Object::Array(vec![Object::Integer(3), Object::Integer(4)]),
+        ),
+        (
+            "filter([1, 2, 3], fn(x) { x == 2 })",
+            Object::Array(vec![Object::Integer(2)]),
+        ),
+        ("filter([], fn(x) { x })", Object::Array(vec![])),
+        (
+            "filter([0, false, \"\", [1]], fn(x) { x })",
+            Object::Array(vec![
+                Object::Integer(0),
+                Object::String(String::new()),
+                Object::Array(vec![Object::Integer(1)]),
+            ]),
+        ),
+        (
+            "filter([1], fn(x) { -true })",
+            Object::Error(String::from("unknown operator: -BOOLEAN")),
+        ),
+        (
+            "filter(\"abc\", fn(x) { true })",
+            Object::Error(String::from(
+                "argument to `filter` must be ARRAY, got STRING",
+            )),
+        ),
+        (
+            "filter([1], [])",
+            Object::Error(String::from(
+                "argument to `filter` must be FUNCTION, got ARRAY",
+            )),
+        ),
+        (
+            "filter()",
+            Object::Error(String::from("wrong number of arguments: expected 2, got 0")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_builtin_reduce_test() {
+    let tests = [
+        (
+            "reduce([1, 2, 3], 0, fn(acc, x) { acc + x })",
+            Object::Integer(6),
+        ),
+        (
+            "reduce([1, 2, 3, 4], 1, fn(acc, x) { acc * x })",
+            Object::Integer(24),
+        ),
+        (
+            "reduce([\"a\", \"b\", \"c\"], \"\", fn(acc, s) { acc + s })",
+            Object::String(String::from("abc")),
+        ),
+        ("reduce([], 7, fn(acc, x) { x })", Object::Integer(7)),
+        (
+            "reduce([[1], [2, 3]], 0, fn(acc, a) { acc + len(a) })",
+            Object::Integer(3),
+        ),
+        (
+            "reduce([1], 0, fn(x) { x })",
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+        (
+            "reduce([1], 0, 1)",
+            Object::Error(String::from(
+                "argument to `reduce` must be FUNCTION, got INTEGER",
+            )),
+        ),
+        (
+            "reduce({}, 0, fn(acc, x) { acc })",
+            Object::Error(String::from("argument to `reduce` must be ARRAY, got HASH")),
+        ),
+        (
+            "reduce([1], fn(acc, x) { acc })",
+            Object::Error(String::from("wrong number of arguments: expected 3, got 2")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_function_statement_test() {
+    let tests = [
+        ("fn add(a, b) { a + b } add(2, 3)", 5),
+        (
+            "fn fib(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10)",
+            55,
+        ),
+        ("fn five() { 5 } let f = five; f()", 5),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{}", input);
+    }
+    assert_eq!(test_eval("fn f() {}"), Object::Null);
+}
+
+#[test]
+fn eval_assign_test() {
+    let tests = [
+        ("let x = 1; x = 2; x;", Object::Integer(2)),
+        ("let x = 1; x = x + 1", Object::Integer(2)),
+        (
+            "let x = 1; let y = 2; x = y = 3; [x, y]",
+            Object::Array(vec![Object::Integer(3), Object::Integer(3)]),
+        ),
+        ("let x = 1; x = \"a\"; x", Object::String(String::from("a"))),
+        ("let x = 1; if (true) { x = 2 }; x", Object::Integer(2)),
+        // a call's parameters are its own
+        (
+            "let x = 1; let f = fn(x) { x = 2; x }; [f(5), x]",
+            Object::Array(vec![Object::Integer(2), Object::Integer(1)]),
+        ),
+        // but a function changes the binding it sees where it was defined
+        (
+            "let x = 1; let f = fn() { x = 2; x }; [f(), x]",
+            Object::Array(vec![Object::Integer(2), Object::Integer(2)]),
+        ),
+        (
+            "x = 1",
+            Object::Error(String::from("identifier not found: x")),
+        ),
+        (
+            "let f = fn() { y = 1 }; f()",
+            Object::Error(String::from("identifier not found: y")),
+        ),
+        (
+            "let x = 1; x = 1 / 0; x",
+            Object::Error(String::from("division by zero")),
+        ),
+        // a counter kept in the scope a function was defined in
+        (
+            "let c = 0; let inc = fn() { c = c + 1 }; inc(); inc(); [inc(), c]",
+            Object::Array(vec![Object::Integer(3), Object::Integer(3)]),
+        ),
+        (
+            "let a = [1, 2]; a[0] = 3; a",
+            Object::Array(vec![Object::Integer(3), Object::Integer(2)]),
+        ),
+        ("let a = [1, 2]; a[1] = 3", Object::Integer(3)),
+        (
+            "let a = [[1], [2]]; a[1][0] = 3; a",
+            Object::Array(vec![
+                Object::Array(vec![Object::Integer(1)]),
+                Object::Array(vec![Object::Integer(3)]),
+            ]),
+        ),
+        (
+            "let a = [1]; let b = a; a[0] = 2; [a[0], b[0]]",
+            Object::Array(vec![Object::Integer(2), Object::Integer(1)]),
+        ),
+        (
+            "let h = {\"a\": 1}; h[\"a\"] = 2; h[\"b\"] = 3; [h[\"a\"], h[\"b\"]]",
+            Object::Array(vec![Object::Integer(2), Object::Integer(3)]),
+        ),
+        (
+            "let a = [1]; a[1] = 2",
+            Object::Error(String::from("index out of range: 1")),
+        ),
+        (
+            "let a = [1]; a[-1] = 2",
+            Object::Error(String::from("index out of range: -1")),
+        ),
+        (
+            "let h = {}; h[fn() {}] = 1",
+            Object::Error(String::from("unusable as hash key: FUNCTION")),
+        ),
+        (
+            "let x = 1; x[0] = 2",
+            Object::Error(String::from(
+                "index operator not supported: INTEGER[INTEGER]",
+            )),
+        ),
+        (
+            "a[0] = 1",
+            Object::Error(String::from("identifier not found: a")),
+        ),
+        (
+            "[1][0] = 2",
+            Object::Error(String::from("can't assign to `[1]`")),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn eval_closure_test() {
+    let tests = [
+        ("let x = 1; let f = fn() { x }; let x = 2; f();", Object::Integer(1)),
+        (
+            "let x = 1; let f = fn() { fn() { x } }; let x = 2; f()()",
+            Object::Integer(1),
+        ),
+        (
+            "let adder = fn(x) { fn(y) { x + y } }; let add2 = adder(2); let x = 10; add2(3)",
+            Object::Integer(5),
+        ),
+        (
+            "let counter = fn(n) { fn() { n } }; let a = counter(1); let b = counter(2); [a(), b()]",
+            Object::Array(vec![Object::Integer(1), Object::Integer(2)]),
+        ),
+        // a function can call itself whether it's defined with `let` or `fn`
+        (
+            "let f = fn(n) { if (n == 0) { 0 } else { n + f(n - 1) } }; let g = f; let f = 5; g(3)",
+            Object::Integer(6),
+        ),
+        (
+            "fn f(n) { if (n == 0) { 0 } else { n + f(n - 1) } } f(3)",
+            Object::Integer(6),
+        ),
+        // binding an existing function to a new name doesn't change what it sees
+        (
+            "let g = 1; let f = fn() { g }; let g = f; f()",
+            Object::Integer(1),
+        ),
+        // names bound after the function was defined are seen, unless they're bound again
+        ("let f = fn() { later }; let later = 1; f()", Object::Integer(1)),
+        (
+            "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } }; [even(10), odd(7), even(7)]",
+            Object::Array(vec![TRUE, TRUE, FALSE]),
+        ),
+        (
+            "let x = 1; let f = fn() { x }; let g = fn() { let x = 3; f() }; g()",
+            Object::Integer(1),
+        ),
+        (
+            "let x = 1; let f = fn() { x }; if (true) { let x = 2; f() }",
+            Object::Integer(1),
+        ),
+        (
+            "let f = fn() { len(\"ab\") }; let len = 5; f()",
+            Object::Integer(2),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn tail_call_test() {
+    let tests = [
+        (
+            "let sum = fn(n, acc) { if (n == 0) { acc } else { sum(n - 1, acc + n) } }; sum(100000, 0)",
+            Object::Integer(5000050000),
+        ),
+        (
+            "let count = fn(n) { if (n == 0) { return \"done\"; } return count(n - 1); }; count(100000)",
+            Object::String(String::from("done")),
+        ),
+        (
+            "let even = fn(n) { if (n == 0) { true } else if (n == 1) { false } else { even(n - 2) } }; even(100001)",
+            Object::Boolean(false),
+        ),
+        (
+            "let fact = fn(n, acc) { if (n == 0) { acc } else { fact(n - 1, acc * n) } }; fact(20, 1)",
+            Object::Integer(2432902008176640000),
+        ),
+        (
+            "let fact = fn(n, acc) { if (n == 0) { acc } else { fact(n - 1, acc * n) } }; fact(100000, 1)",
+            Object::Error(String::from("integer overflow")),
+        ),
+        (
+            "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1, 1) } }; f(3)",
+            Object::Error(String::from("wrong number of arguments: expected 1, got 2")),
+        ),
+        (
+            "let f = fn(n) { if (n == 0) { x } else { f(n - 1) } }; f(3)",
+            Object::Error(String::from("identifier not found: x")),
+        ),
+        (
+            "let f = fn(n) { let m = n - 1; if (m < 0) { return 0; } f(m) }; f(100000)",
+            Object::Integer(0),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(test_eval(input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn same_function_test() {
+    let mut env = Rc::new(RefCell::new(Environment::new()));
+    let prog = crate::parse("let f = fn(x) { x }; let g = fn(x) { x };").unwrap();
+    eval_program(&prog, &mut env);
+    let f = env.borrow().get("f").unwrap();
+    let g = env.borrow().get("g").unwrap();
+
+    assert!(is_same_function(&f, &f.clone()));
+    assert!(!is_same_function(&f, &g));
+}
+
+#[test]
+fn recursion_limit_test() {
+    // test threads only get 2MiB of stack, not enough for the limit
+    let evaluator = std::thread::Builder::new().stack_size(8 << 20);
+    evaluator
+        .spawn(|| {
+            // not tail calls, so these recurse
+            let countdown =
+                "let countdown = fn(n) { if (n == 0) { 0 } else { 1 + countdown(n - 1) } };";
+            let tests = [
+                (format!("{} countdown(500)", countdown), Object::Integer(500)),
+                (
+                    format!("{} countdown(600)", countdown),
+                    Object::Error(String::from("recursion limit exceeded")),
+                ),
+                (
+                    String::from("let f = fn() { 1 + f() }; f()"),
+                    Object::Error(String::from("recursion limit exceeded")),
+                ),
+                (
+                    String::from("let ping = fn(n) { if (n == 0) { 0 } else { pong(n - 1) } }; let pong = fn(n) { ping(n) }; ping(600)"),
+                    Object::Error(String::from("recursion limit exceeded")),
+                ),
+                // calls made by builtins count too
+                (
+                    String::from("let f = fn(n) { map([n], f) }; f(1)"),
+                    Object::Error(String::from("recursion limit exceeded")),
+                ),
+            ];
+            for (input, expected) in tests {
+                assert_eq!(test_eval(&input), expected, "{}", input);
+            }
+
+            // the calls that hit the limit have all returned by the next one
+            let mut env = Rc::new(RefCell::new(Environment::new()));
+            let prog = crate::parse(&format!("{} countdown(600)", countdown)).unwrap();
+            eval_program(&prog, &mut env);
+            let prog = crate::parse("countdown(10)").unwrap();
+            assert_eq!(eval_program(&prog, &mut env), Object::Integer(10));
+
+            assert_eq!(
+                eval_program_with_max_depth(&prog, &mut env, 5),
+                Object::Error(String::from("recursion limit exceeded"))
+            );
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
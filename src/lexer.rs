@@ -1,44 +1,85 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::rc::Rc;
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LimiterToken {
     Comma,
+    Colon,
     Semicolon,
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
-    Illegal,
+    /// Source text the lexer could not make sense of, kept so the lexer can step over it.
+    Illegal(String),
     Eof,
-    Identifier(String),
+    Identifier(Rc<str>),
     Literal(String),
+    StringLiteral(String),
+    Char(char),
     Limiter(LimiterToken),
     Assign,
     Plus,
     Minus,
     Bang,
     Asterisk,
+    Power,
     Slash,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
     LT,
     GT,
+    LTEq,
+    GTEq,
     EQ,
     NotEq,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
     Function,
     Let,
     True,
     False,
+    Null,
     If,
     Else,
     Return,
+    While,
+    For,
+    Macro,
+    /// Only produced by `Lexer::with_trivia`.
+    Whitespace(String),
+    /// Only produced by `Lexer::with_trivia`, includes the leading `//` (or `#!` for a shebang).
+    Comment(String),
 }
 
 impl Token {
     fn len(&self) -> usize {
         match self {
-            Token::Illegal | Token::Eof => 0,
-            Token::Identifier(s) | Token::Literal(s) => s.len(),
+            Token::Eof => 0,
+            Token::Illegal(s) | Token::Whitespace(s) | Token::Comment(s) => s.chars().count(),
+            Token::Identifier(s) => s.chars().count(),
+            Token::Literal(s) => s.chars().count(),
+            // quotes included, escapes make the source wider which `read_string` accounts for
+            Token::StringLiteral(s) => s.chars().count() + 2,
+            Token::Char(_) => 3,
             Token::Limiter(_)
             | Token::Assign
             | Token::Plus
@@ -47,55 +88,209 @@ impl Token {
             | Token::Asterisk
             | Token::Slash
             | Token::LT
-            | Token::GT => 1,
-            Token::Function | Token::If | Token::EQ | Token::NotEq => 2,
-            Token::Let => 3,
-            Token::True | Token::Else => 4,
-            Token::False => 5,
+            | Token::GT
+            | Token::BitAnd
+            | Token::BitOr
+            | Token::BitXor
+            | Token::BitNot => 1,
+            Token::Function
+            | Token::If
+            | Token::EQ
+            | Token::NotEq
+            | Token::Power
+            | Token::LTEq
+            | Token::GTEq
+            | Token::And
+            | Token::Or
+            | Token::Shl
+            | Token::Shr
+            | Token::PlusAssign
+            | Token::MinusAssign
+            | Token::AsteriskAssign
+            | Token::SlashAssign => 2,
+            Token::Let | Token::For => 3,
+            Token::True | Token::Else | Token::Null => 4,
+            Token::False | Token::While | Token::Macro => 5,
             Token::Return => 6,
         }
     }
 }
 
+/// Writes tokens back as source text. String and char literals come out with their escapes
+/// normalised, so a raw string is written as the equivalent `"..."` literal.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Eof => Ok(()),
+            Token::Illegal(s) | Token::Literal(s) | Token::Whitespace(s) | Token::Comment(s) => {
+                write!(f, "{}", s)
+            }
+            Token::Identifier(s) => write!(f, "{}", s),
+            Token::StringLiteral(s) => {
+                let escaped: String = s.chars().map(|ch| escape(ch, '"')).collect();
+                write!(f, "\"{}\"", escaped)
+            }
+            Token::Char(ch) => write!(f, "'{}'", escape(*ch, '\'')),
+            Token::Limiter(l) => write!(
+                f,
+                "{}",
+                match l {
+                    LimiterToken::Comma => ",",
+                    LimiterToken::Colon => ":",
+                    LimiterToken::Semicolon => ";",
+                    LimiterToken::LParen => "(",
+                    LimiterToken::RParen => ")",
+                    LimiterToken::LBrace => "{",
+                    LimiterToken::RBrace => "}",
+                    LimiterToken::LBracket => "[",
+                    LimiterToken::RBracket => "]",
+                }
+            ),
+            Token::Assign => write!(f, "="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Bang => write!(f, "!"),
+            Token::Asterisk => write!(f, "*"),
+            Token::Power => write!(f, "**"),
+            Token::Slash => write!(f, "/"),
+            Token::PlusAssign => write!(f, "+="),
+            Token::MinusAssign => write!(f, "-="),
+            Token::AsteriskAssign => write!(f, "*="),
+            Token::SlashAssign => write!(f, "/="),
+            Token::LT => write!(f, "<"),
+            Token::GT => write!(f, ">"),
+            Token::LTEq => write!(f, "<="),
+            Token::GTEq => write!(f, ">="),
+            Token::EQ => write!(f, "=="),
+            Token::NotEq => write!(f, "!="),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::BitAnd => write!(f, "&"),
+            Token::BitOr => write!(f, "|"),
+            Token::BitXor => write!(f, "^"),
+            Token::BitNot => write!(f, "~"),
+            Token::Shl => write!(f, "<<"),
+            Token::Shr => write!(f, ">>"),
+            Token::Function => write!(f, "fn"),
+            Token::Let => write!(f, "let"),
+            Token::True => write!(f, "true"),
+            Token::False => write!(f, "false"),
+            Token::Null => write!(f, "null"),
+            Token::If => write!(f, "if"),
+            Token::Else => write!(f, "else"),
+            Token::Return => write!(f, "return"),
+            Token::While => write!(f, "while"),
+            Token::For => write!(f, "for"),
+            Token::Macro => write!(f, "macro"),
+        }
+    }
+}
+
 fn is_letter(ch: char) -> bool {
     // allowing `_` for identifiers
     ch.is_ascii_alphabetic() || ch == '_'
 }
 
+/// Identifiers start with a letter but may contain digits after that, only ASCII ones.
+fn is_identifier_char(ch: char) -> bool {
+    is_letter(ch) || ch.is_ascii_digit()
+}
+
 fn is_digit(ch: char) -> bool {
-    ch.is_numeric()
+    ch.is_ascii_digit()
 }
 
 fn is_whitespace(ch: char) -> bool {
     ch == ' ' || ch == '\t' || ch == '\n' || ch == '\r'
 }
 
-fn look_up_identifier(ident: String) -> Token {
-    return match ident.as_str() {
-        "fn" => Token::Function,
-        "let" => Token::Let,
-        "true" => Token::True,
-        "false" => Token::False,
-        "if" => Token::If,
-        "else" => Token::Else,
-        "return" => Token::Return,
-        _ => Token::Identifier(ident),
-    };
+/// The reverse of `unescape`, for writing `ch` inside a literal delimited by `quote`.
+fn escape(ch: char, quote: char) -> String {
+    match ch {
+        '\n' => String::from("\\n"),
+        '\t' => String::from("\\t"),
+        '\r' => String::from("\\r"),
+        '\0' => String::from("\\0"),
+        '\\' => String::from("\\\\"),
+        _ if ch == quote => format!("\\{}", ch),
+        _ => ch.to_string(),
+    }
+}
+
+fn unescape(ch: char) -> Option<char> {
+    match ch {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        _ => None,
+    }
+}
+
+/// A place in the input, both counting from 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
 }
 
+/// The start of the input.
+impl Default for Position {
+    fn default() -> Self {
+        Self { line: 1, column: 1 }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Where a token or node is in the input, from its first char up to just past its last.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Something the lexer couldn't make sense of. An `Illegal` token is still emitted in its place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexError {
+    pub position: Position,
+    pub found: char,
+    pub message: String,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for LexError {}
+
 pub struct Lexer {
     input: String,
     read_position: usize,
     ch: Option<char>,
+    position: Position,
+    token_position: Position,
+    errors: Vec<LexError>,
+    // every name seen so far, so repeated identifiers share one allocation
+    identifiers: HashSet<Rc<str>>,
+    // set once the end of the input is reached, from then on `next` only returns `None`
+    finished: bool,
+    keep_trivia: bool,
 }
 
 impl From<String> for Lexer {
     fn from(string: String) -> Self {
-        let mut l = Lexer {
-            input: string,
-            read_position: 0,
-            ch: None,
-        };
+        let mut l = Lexer::new(string, false);
+        l.skip_shebang();
         l.read_char();
         l
     }
@@ -105,57 +300,211 @@ impl Iterator for Lexer {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.skip_white_spaces();
-        let token: Token = match self.ch {
-            None => Token::Eof,
-            Some(x) => match x {
-                ',' => Token::Limiter(LimiterToken::Comma),
-                ';' => Token::Limiter(LimiterToken::Semicolon),
-                '(' => Token::Limiter(LimiterToken::LParen),
-                ')' => Token::Limiter(LimiterToken::RParen),
-                '{' => Token::Limiter(LimiterToken::LBrace),
-                '}' => Token::Limiter(LimiterToken::RBrace),
-                '+' => Token::Plus,
-                '-' => Token::Minus,
-                '=' => {
-                    if let Some('=') = self.peek_char_head() {
-                        Token::EQ
-                    } else {
-                        Token::Assign
+        if self.finished {
+            return None;
+        }
+        if !self.keep_trivia {
+            self.skip_white_spaces();
+        }
+        self.token_position = self.position;
+        let (token, width): (Token, usize) = match self.ch {
+            None => (Token::Eof, 0),
+            Some(x) if self.keep_trivia && is_whitespace(x) => self.read_whitespace(),
+            Some('/') if self.keep_trivia && self.comment_length(self.read_position) > 0 => {
+                self.read_comment()
+            }
+            Some('#')
+                if self.keep_trivia
+                    && self.read_position == 0
+                    && self.peek_char_head() == Some('!') =>
+            {
+                self.read_comment()
+            }
+            // literals can contain escapes so their width in the source is worked out while reading
+            Some('"') => self.read_string(),
+            Some('\'') => self.read_char_literal(),
+            Some('r') if self.raw_string_hashes().is_some() => self.read_raw_string(),
+            Some(x) => {
+                let token = match x {
+                    ',' => Token::Limiter(LimiterToken::Comma),
+                    ':' => Token::Limiter(LimiterToken::Colon),
+                    ';' => Token::Limiter(LimiterToken::Semicolon),
+                    '(' => Token::Limiter(LimiterToken::LParen),
+                    ')' => Token::Limiter(LimiterToken::RParen),
+                    '{' => Token::Limiter(LimiterToken::LBrace),
+                    '}' => Token::Limiter(LimiterToken::RBrace),
+                    '[' => Token::Limiter(LimiterToken::LBracket),
+                    ']' => Token::Limiter(LimiterToken::RBracket),
+                    '+' => {
+                        if let Some('=') = self.peek_char_head() {
+                            Token::PlusAssign
+                        } else {
+                            Token::Plus
+                        }
                     }
-                }
-                '!' => {
-                    if let Some('=') = self.peek_char_head() {
-                        Token::NotEq
-                    } else {
-                        Token::Bang
+                    '-' => {
+                        if let Some('=') = self.peek_char_head() {
+                            Token::MinusAssign
+                        } else {
+                            Token::Minus
+                        }
                     }
-                }
-                '*' => Token::Asterisk,
-                '/' => Token::Slash,
-                '<' => Token::LT,
-                '>' => Token::GT,
-                _ => {
-                    if is_letter(x) {
-                        look_up_identifier(self.read_identifier())
-                    } else if is_digit(x) {
-                        Token::Literal(self.read_number())
-                    } else {
-                        Token::Illegal
+                    '=' => {
+                        if let Some('=') = self.peek_char_head() {
+                            Token::EQ
+                        } else {
+                            Token::Assign
+                        }
                     }
-                }
-            },
+                    '!' => {
+                        if let Some('=') = self.peek_char_head() {
+                            Token::NotEq
+                        } else {
+                            Token::Bang
+                        }
+                    }
+                    '*' => match self.peek_char_head() {
+                        Some('*') => Token::Power,
+                        Some('=') => Token::AsteriskAssign,
+                        _ => Token::Asterisk,
+                    },
+                    '/' => {
+                        if let Some('=') = self.peek_char_head() {
+                            Token::SlashAssign
+                        } else {
+                            Token::Slash
+                        }
+                    }
+                    '<' => match self.peek_char_head() {
+                        Some('<') => Token::Shl,
+                        Some('=') => Token::LTEq,
+                        _ => Token::LT,
+                    },
+                    '>' => match self.peek_char_head() {
+                        Some('>') => Token::Shr,
+                        Some('=') => Token::GTEq,
+                        _ => Token::GT,
+                    },
+                    '&' => {
+                        if let Some('&') = self.peek_char_head() {
+                            Token::And
+                        } else {
+                            Token::BitAnd
+                        }
+                    }
+                    '|' => {
+                        if let Some('|') = self.peek_char_head() {
+                            Token::Or
+                        } else {
+                            Token::BitOr
+                        }
+                    }
+                    '^' => Token::BitXor,
+                    '~' => Token::BitNot,
+                    _ => {
+                        if is_letter(x) {
+                            let ident = self.read_identifier();
+                            self.look_up_identifier(ident)
+                        } else if is_digit(x) {
+                            Token::Literal(self.read_number())
+                        } else {
+                            self.error(format!("unexpected character '{}'", x));
+                            Token::Illegal(x.to_string())
+                        }
+                    }
+                };
+                let width = token.len();
+                (token, width)
+            }
         };
         if token == Token::Eof {
+            self.finished = true;
             return None;
         }
-        self.increment_read_position(&token);
+        self.advance(width);
         self.read_char();
         Some(token)
     }
 }
 
+impl FusedIterator for Lexer {}
+
+/// Lexes the whole of `input`, giving back either all of its tokens or everything that was wrong
+/// with it.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, Vec<LexError>> {
+    let mut lex = Lexer::from(String::from(input));
+    let tokens: Vec<Token> = lex.by_ref().collect();
+    if lex.errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(lex.errors)
+    }
+}
+
 impl Lexer {
+    fn new(input: String, keep_trivia: bool) -> Self {
+        Lexer {
+            input,
+            read_position: 0,
+            ch: None,
+            position: Position { line: 1, column: 1 },
+            token_position: Position { line: 1, column: 1 },
+            errors: vec![],
+            identifiers: HashSet::new(),
+            finished: false,
+            keep_trivia,
+        }
+    }
+
+    /// A lexer that also yields the whitespace and comments (a shebang line included) between
+    /// tokens, so the tokens written back to back reproduce the input.
+    pub fn with_trivia(input: String) -> Self {
+        let mut l = Lexer::new(input, true);
+        l.read_char();
+        l
+    }
+
+    /// Problems found in the input so far, in the order they were found.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Where the last token returned by `next` starts, or the end of the input once there are
+    /// no tokens left.
+    pub fn token_position(&self) -> Position {
+        self.token_position
+    }
+
+    /// Where the last token returned by `next` is, or an empty span at the end of the input once
+    /// there are no tokens left.
+    pub fn token_span(&self) -> Span {
+        Span {
+            start: self.token_position,
+            end: self.position,
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(LexError {
+            position: self.position,
+            found: self.ch.unwrap_or_default(),
+            message,
+        });
+    }
+
+    /// Moves `read_position` forward keeping track of the line and column it ends up on.
+    fn advance(&mut self, width: usize) {
+        for ch in self.input.chars().skip(self.read_position).take(width) {
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.read_position += width;
+    }
+
     fn read_char(&mut self) {
         // FIXME: it's probably bad to create the iterator everytime
         // However having `input` as the iterator means that when reading `take_while` requires a
@@ -163,45 +512,250 @@ impl Lexer {
         self.ch = self.input.chars().nth(self.read_position);
     }
 
+    /// Skips a `#!` line at the very start of the input so scripts can be made executable. The
+    /// line break is left in place for the usual whitespace handling.
+    fn skip_shebang(&mut self) {
+        if self.input.starts_with("#!") {
+            self.advance(self.input.chars().take_while(|c| *c != '\n').count());
+        }
+    }
+
     fn peek_char_head(&self) -> Option<char> {
-        return self.input.chars().nth(self.read_position + 1);
+        self.input.chars().nth(self.read_position + 1)
     }
 
-    fn increment_read_position(&mut self, token: &Token) {
-        self.read_position += token.len();
+    fn look_up_identifier(&mut self, ident: String) -> Token {
+        match ident.as_str() {
+            "fn" => Token::Function,
+            "let" => Token::Let,
+            "true" => Token::True,
+            "false" => Token::False,
+            "null" => Token::Null,
+            "if" => Token::If,
+            "else" => Token::Else,
+            "return" => Token::Return,
+            "while" => Token::While,
+            "for" => Token::For,
+            "macro" => Token::Macro,
+            _ => Token::Identifier(self.intern(ident)),
+        }
+    }
+
+    fn intern(&mut self, ident: String) -> Rc<str> {
+        if let Some(interned) = self.identifiers.get(ident.as_str()) {
+            return Rc::clone(interned);
+        }
+        let interned: Rc<str> = Rc::from(ident);
+        self.identifiers.insert(Rc::clone(&interned));
+        interned
     }
 
     fn read_identifier(&self) -> String {
-        return self
-            .input
+        self.input
             .chars()
             .skip(self.read_position)
-            .take_while(|ch| is_letter(*ch))
-            .collect::<String>();
+            .take_while(|ch| is_identifier_char(*ch))
+            .collect::<String>()
     }
 
     fn read_number(&self) -> String {
-        return self
-            .input
+        self.input
             .chars()
             .skip(self.read_position)
             .take_while(|ch| is_digit(*ch))
+            .collect::<String>()
+    }
+
+    /// Reads a `"..."` literal starting at the current char, returning the token and how many
+    /// chars of input it spans. Unknown escapes and a missing closing quote give an `Illegal`
+    /// token covering the offending source.
+    fn read_string(&mut self) -> (Token, usize) {
+        let mut chars = self.input.chars().skip(self.read_position + 1);
+        let mut value = String::new();
+        let mut width = 1;
+        while let Some(ch) = chars.next() {
+            width += 1;
+            match ch {
+                '"' => return (Token::StringLiteral(value), width),
+                '\\' => match chars.next().and_then(unescape) {
+                    Some(escaped) => {
+                        width += 1;
+                        value.push(escaped);
+                    }
+                    None => {
+                        return self.illegal_until_closing_quote(
+                            width,
+                            '"',
+                            "unknown escape sequence in string literal",
+                        )
+                    }
+                },
+                _ => value.push(ch),
+            }
+        }
+        self.illegal_until_closing_quote(width, '"', "unterminated string literal")
+    }
+
+    /// How many `#` are between the `r` the current char is on and a `"` after them, if this is
+    /// the start of a raw string at all.
+    fn raw_string_hashes(&self) -> Option<usize> {
+        let hashes = self
+            .input
+            .chars()
+            .skip(self.read_position + 1)
+            .take_while(|ch| *ch == '#')
+            .count();
+        let quote = self.input.chars().nth(self.read_position + 1 + hashes);
+        (quote == Some('"')).then_some(hashes)
+    }
+
+    /// Reads a `r"..."` literal where backslashes are kept as they are. A `"` can't be escaped,
+    /// instead the quotes can be marked with as many `#` as needed, as in `r#"say "hi""#`, and
+    /// the literal ends at the first `"` followed by that many `#`.
+    fn read_raw_string(&mut self) -> (Token, usize) {
+        let hashes = self.raw_string_hashes().unwrap_or(0);
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let opening = hashes + 2;
+        let rest: String = self
+            .input
+            .chars()
+            .skip(self.read_position + opening)
+            .collect();
+        match rest.find(&closing) {
+            Some(end) => {
+                let value = String::from(&rest[..end]);
+                let width = opening + value.chars().count() + closing.chars().count();
+                (Token::StringLiteral(value), width)
+            }
+            None if hashes == 0 => {
+                self.illegal_until_closing_quote(opening, '"', "unterminated raw string literal")
+            }
+            // without the closing quote and `#` it can only run to the end of the input
+            None => {
+                self.error(format!(
+                    "unterminated raw string literal, expected it to end with `{}`",
+                    closing
+                ));
+                let width = opening + rest.chars().count();
+                let text = self.input.chars().skip(self.read_position).collect();
+                (Token::Illegal(text), width)
+            }
+        }
+    }
+
+    /// Reads a `'c'` literal holding exactly one (possibly escaped) char.
+    fn read_char_literal(&mut self) -> (Token, usize) {
+        let mut chars = self.input.chars().skip(self.read_position + 1);
+        let (value, width) = match chars.next() {
+            Some('\\') => match chars.next().and_then(unescape) {
+                Some(ch) => (ch, 3),
+                None => {
+                    return self.illegal_until_closing_quote(
+                        2,
+                        '\'',
+                        "unknown escape sequence in character literal",
+                    )
+                }
+            },
+            Some('\'') => {
+                return self.illegal_until_closing_quote(1, '\'', "empty character literal")
+            }
+            Some('\n') | None => {
+                return self.illegal_until_closing_quote(1, '\'', "unterminated character literal")
+            }
+            Some(ch) => (ch, 2),
+        };
+        match chars.next() {
+            Some('\'') => (Token::Char(value), width + 1),
+            Some('\n') | None => {
+                self.illegal_until_closing_quote(width, '\'', "unterminated character literal")
+            }
+            Some(_) => self.illegal_until_closing_quote(
+                width,
+                '\'',
+                "character literal must hold exactly one character",
+            ),
+        }
+    }
+
+    /// Builds the `Illegal` token for a broken literal, swallowing the rest of it so lexing
+    /// resumes after its closing quote. Strings may span lines so without a closing quote they
+    /// run to the end of the input, a char literal stops at the end of its line.
+    fn illegal_until_closing_quote(
+        &mut self,
+        consumed: usize,
+        quote: char,
+        message: &str,
+    ) -> (Token, usize) {
+        self.error(String::from(message));
+        let rest = self
+            .input
+            .chars()
+            .skip(self.read_position + consumed)
+            .take_while(|ch| *ch != quote && (quote == '"' || *ch != '\n'))
+            .count();
+        let mut width = consumed + rest;
+        if self.input.chars().nth(self.read_position + width) == Some(quote) {
+            width += 1;
+        }
+        let text = self
+            .input
+            .chars()
+            .skip(self.read_position)
+            .take(width)
             .collect::<String>();
+        (Token::Illegal(text), width)
     }
 
+    /// Skips whitespace and `//` comments, which run until the end of the line.
     fn skip_white_spaces(&mut self) {
-        let spaces = self
+        loop {
+            let spaces = self
+                .input
+                .chars()
+                .skip(self.read_position)
+                .take_while(|c| is_whitespace(*c))
+                .count();
+            let comment = self.comment_length(self.read_position + spaces);
+
+            // spaces don't create a token so we much increment and re-read the next char
+            if spaces + comment == 0 {
+                break;
+            }
+            self.advance(spaces + comment);
+            self.read_char();
+        }
+    }
+
+    fn read_whitespace(&self) -> (Token, usize) {
+        let text = self
             .input
             .chars()
             .skip(self.read_position)
             .take_while(|c| is_whitespace(*c))
-            .count();
+            .collect::<String>();
+        let width = text.chars().count();
+        (Token::Whitespace(text), width)
+    }
 
-        // spaces don't create a token so we much increment and re-read the next char
-        if spaces > 0 {
-            self.read_position += spaces;
-            self.read_char();
+    /// Reads a comment running until the end of the line, the line break isn't part of it.
+    fn read_comment(&self) -> (Token, usize) {
+        let text = self
+            .input
+            .chars()
+            .skip(self.read_position)
+            .take_while(|c| *c != '\n')
+            .collect::<String>();
+        let width = text.chars().count();
+        (Token::Comment(text), width)
+    }
+
+    fn comment_length(&self, position: usize) -> usize {
+        let mut chars = self.input.chars().skip(position);
+        if chars.next() != Some('/') || chars.next() != Some('/') {
+            return 0;
         }
+        2 + chars.take_while(|c| *c != '\n').count()
     }
 }
 
@@ -229,39 +783,39 @@ fn lexer_test() {
 
     let tests: [Token; 73] = [
         Token::Let,
-        Token::Identifier(String::from("five")),
+        Token::Identifier("five".into()),
         Token::Assign,
         Token::Literal(String::from("5")),
         Token::Limiter(LimiterToken::Semicolon),
         Token::Let,
-        Token::Identifier(String::from("ten")),
+        Token::Identifier("ten".into()),
         Token::Assign,
         Token::Literal(String::from("10")),
         Token::Limiter(LimiterToken::Semicolon),
         Token::Let,
-        Token::Identifier(String::from("add")),
+        Token::Identifier("add".into()),
         Token::Assign,
         Token::Function,
         Token::Limiter(LimiterToken::LParen),
-        Token::Identifier(String::from("x")),
+        Token::Identifier("x".into()),
         Token::Limiter(LimiterToken::Comma),
-        Token::Identifier(String::from("y")),
+        Token::Identifier("y".into()),
         Token::Limiter(LimiterToken::RParen),
         Token::Limiter(LimiterToken::LBrace),
-        Token::Identifier(String::from("x")),
+        Token::Identifier("x".into()),
         Token::Plus,
-        Token::Identifier(String::from("y")),
+        Token::Identifier("y".into()),
         Token::Limiter(LimiterToken::Semicolon),
         Token::Limiter(LimiterToken::RBrace),
         Token::Limiter(LimiterToken::Semicolon),
         Token::Let,
-        Token::Identifier(String::from("result")),
+        Token::Identifier("result".into()),
         Token::Assign,
-        Token::Identifier(String::from("add")),
+        Token::Identifier("add".into()),
         Token::Limiter(LimiterToken::LParen),
-        Token::Identifier(String::from("five")),
+        Token::Identifier("five".into()),
         Token::Limiter(LimiterToken::Comma),
-        Token::Identifier(String::from("ten")),
+        Token::Identifier("ten".into()),
         Token::Limiter(LimiterToken::RParen),
         Token::Limiter(LimiterToken::Semicolon),
         Token::Bang,
@@ -304,9 +858,667 @@ fn lexer_test() {
     ];
 
     lex.into_iter()
-        .zip(tests.into_iter())
+        .zip(tests)
         .map(|(token, test_token)| {
             assert_eq!(token, test_token);
         })
         .for_each(drop);
 }
+
+#[test]
+fn string_literal_test() {
+    let input = r#"let s = "foo bar"; "a\"b\\n\n" "";"#;
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier("s".into()),
+            Token::Assign,
+            Token::StringLiteral(String::from("foo bar")),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::StringLiteral(String::from("a\"b\\n\n")),
+            Token::StringLiteral(String::new()),
+            Token::Limiter(LimiterToken::Semicolon),
+        ]
+    );
+}
+
+#[test]
+fn raw_string_literal_test() {
+    let input = r#"r"C:\path\no\escapes" r"" r + "\\d""#;
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::StringLiteral(String::from(r"C:\path\no\escapes")),
+            Token::StringLiteral(String::new()),
+            Token::Identifier("r".into()),
+            Token::Plus,
+            Token::StringLiteral(String::from(r"\d")),
+        ]
+    );
+}
+
+#[test]
+fn raw_string_with_hashes_test() {
+    let input = r###"r#"say "hi""# r##"a "# b"## r#""# 5"###;
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::StringLiteral(String::from(r#"say "hi""#)),
+            Token::StringLiteral(String::from(r##"a "# b"##)),
+            Token::StringLiteral(String::new()),
+            Token::Literal(String::from("5")),
+        ]
+    );
+
+    let mut lexer = Lexer::from(String::from(r##"let s = r#"say "hi"; 5"##));
+    let tokens: Vec<Token> = lexer.by_ref().collect();
+    assert_eq!(
+        tokens[3..],
+        [Token::Illegal(String::from(r##"r#"say "hi"; 5"##))]
+    );
+    assert_eq!(
+        lexer.errors()[0].message,
+        "unterminated raw string literal, expected it to end with `\"#`"
+    );
+
+    // `r` followed by `#` but no quote is just the identifier
+    let tokens: Vec<Token> = Lexer::from(String::from("r#")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("r".into()),
+            Token::Illegal(String::from("#"))
+        ]
+    );
+}
+
+#[test]
+fn unterminated_string_literal_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from(r#"let p = r"C:\tmp;"#)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier("p".into()),
+            Token::Assign,
+            Token::Illegal(String::from(r#"r"C:\tmp;"#)),
+        ]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from(r#""bad \q escape" 5"#)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Illegal(String::from(r#""bad \q escape""#)),
+            Token::Literal(String::from("5")),
+        ]
+    );
+}
+
+#[test]
+fn char_literal_test() {
+    let input = r"'a' '\n' '\'' '\\' ' '";
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Char('a'),
+            Token::Char('\n'),
+            Token::Char('\''),
+            Token::Char('\\'),
+            Token::Char(' '),
+        ]
+    );
+}
+
+#[test]
+fn invalid_char_literal_test() {
+    let input = "'ab' x '' y '\\q' z\n'c";
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Illegal(String::from("'ab'")),
+            Token::Identifier("x".into()),
+            Token::Illegal(String::from("''")),
+            Token::Identifier("y".into()),
+            Token::Illegal(String::from("'\\q'")),
+            Token::Identifier("z".into()),
+            Token::Illegal(String::from("'c")),
+        ]
+    );
+
+    // an unterminated literal stops at the end of its line
+    let tokens: Vec<Token> = Lexer::from(String::from("'x\nlet")).collect();
+    assert_eq!(tokens, vec![Token::Illegal(String::from("'x")), Token::Let]);
+}
+
+#[test]
+fn power_operator_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("2**3")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Literal(String::from("2")),
+            Token::Power,
+            Token::Literal(String::from("3")),
+        ]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from("2 * *p")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Literal(String::from("2")),
+            Token::Asterisk,
+            Token::Asterisk,
+            Token::Identifier("p".into()),
+        ]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from("a ***")).collect();
+    assert_eq!(
+        tokens,
+        vec![Token::Identifier("a".into()), Token::Power, Token::Asterisk,]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from("**")).collect();
+    assert_eq!(tokens, vec![Token::Power]);
+}
+
+#[test]
+fn bitwise_operators_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("a << 2 >= b >> 1")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("a".into()),
+            Token::Shl,
+            Token::Literal(String::from("2")),
+            Token::GTEq,
+            Token::Identifier("b".into()),
+            Token::Shr,
+            Token::Literal(String::from("1")),
+        ]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from("x & y && z")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("x".into()),
+            Token::BitAnd,
+            Token::Identifier("y".into()),
+            Token::And,
+            Token::Identifier("z".into()),
+        ]
+    );
+
+    let tokens: Vec<Token> = Lexer::from(String::from("~a|b||c^d<=e<f>g&&&h")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::BitNot,
+            Token::Identifier("a".into()),
+            Token::BitOr,
+            Token::Identifier("b".into()),
+            Token::Or,
+            Token::Identifier("c".into()),
+            Token::BitXor,
+            Token::Identifier("d".into()),
+            Token::LTEq,
+            Token::Identifier("e".into()),
+            Token::LT,
+            Token::Identifier("f".into()),
+            Token::GT,
+            Token::Identifier("g".into()),
+            Token::And,
+            Token::BitAnd,
+            Token::Identifier("h".into()),
+        ]
+    );
+}
+
+#[test]
+fn compound_assignment_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("a += 1; b -= 2; c *= 3; d /= 4;")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("a".into()),
+            Token::PlusAssign,
+            Token::Literal(String::from("1")),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("b".into()),
+            Token::MinusAssign,
+            Token::Literal(String::from("2")),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("c".into()),
+            Token::AsteriskAssign,
+            Token::Literal(String::from("3")),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("d".into()),
+            Token::SlashAssign,
+            Token::Literal(String::from("4")),
+            Token::Limiter(LimiterToken::Semicolon),
+        ]
+    );
+
+    // spaces keep the operator and the `=` apart, and `**=` is a power followed by an assign
+    let tokens: Vec<Token> = Lexer::from(String::from("a + = b **= c")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("a".into()),
+            Token::Plus,
+            Token::Assign,
+            Token::Identifier("b".into()),
+            Token::Power,
+            Token::Assign,
+            Token::Identifier("c".into()),
+        ]
+    );
+}
+
+#[test]
+fn comment_test() {
+    let input = "a /= b // half
+    // a whole line /= of comment
+    c / d //";
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("a".into()),
+            Token::SlashAssign,
+            Token::Identifier("b".into()),
+            Token::Identifier("c".into()),
+            Token::Slash,
+            Token::Identifier("d".into()),
+        ]
+    );
+}
+
+#[test]
+fn brackets_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("[1, a][0]")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Limiter(LimiterToken::LBracket),
+            Token::Literal(String::from("1")),
+            Token::Limiter(LimiterToken::Comma),
+            Token::Identifier("a".into()),
+            Token::Limiter(LimiterToken::RBracket),
+            Token::Limiter(LimiterToken::LBracket),
+            Token::Literal(String::from("0")),
+            Token::Limiter(LimiterToken::RBracket),
+        ]
+    );
+}
+
+#[test]
+fn shebang_test() {
+    let script = "let x = 5;\nx;";
+    let with_shebang = format!("#!/usr/bin/env monkey\n{}", script);
+
+    let expected: Vec<Token> = Lexer::from(String::from(script)).collect();
+    let tokens: Vec<Token> = Lexer::from(with_shebang).collect();
+    assert_eq!(tokens, expected);
+
+    let tokens: Vec<Token> = Lexer::from(String::from("#!/usr/bin/env monkey")).collect();
+    assert_eq!(tokens, vec![]);
+}
+
+#[test]
+fn hash_outside_shebang_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from(" #!x\n#")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Illegal(String::from("#")),
+            Token::Bang,
+            Token::Identifier("x".into()),
+            Token::Illegal(String::from("#")),
+        ]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip_test() {
+    let input = "let s = \"hi\"; add(s, 5) == 'c';";
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+
+    let json = serde_json::to_value(&tokens).unwrap();
+    assert_eq!(json[0], serde_json::json!("Let"));
+    assert_eq!(json[1], serde_json::json!({ "Identifier": "s" }));
+    assert_eq!(json[3], serde_json::json!({ "StringLiteral": "hi" }));
+    assert_eq!(json[4], serde_json::json!({ "Limiter": "Semicolon" }));
+    assert_eq!(json[9], serde_json::json!({ "Literal": "5" }));
+
+    let back: Vec<Token> = serde_json::from_value(json).unwrap();
+    assert_eq!(back, tokens);
+}
+
+#[test]
+fn lex_errors_test() {
+    let mut lex = Lexer::from(String::from("a @ $\n  let x# = 5;"));
+    let tokens: Vec<Token> = lex.by_ref().collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("a".into()),
+            Token::Illegal(String::from("@")),
+            Token::Illegal(String::from("$")),
+            Token::Let,
+            Token::Identifier("x".into()),
+            Token::Illegal(String::from("#")),
+            Token::Assign,
+            Token::Literal(String::from("5")),
+            Token::Limiter(LimiterToken::Semicolon),
+        ]
+    );
+    assert_eq!(
+        lex.errors(),
+        [
+            LexError {
+                position: Position { line: 1, column: 3 },
+                found: '@',
+                message: String::from("unexpected character '@'"),
+            },
+            LexError {
+                position: Position { line: 1, column: 5 },
+                found: '$',
+                message: String::from("unexpected character '$'"),
+            },
+            LexError {
+                position: Position { line: 2, column: 8 },
+                found: '#',
+                message: String::from("unexpected character '#'"),
+            },
+        ]
+    );
+    assert_eq!(
+        lex.errors()[2].to_string(),
+        "unexpected character '#' at line 2, column 8"
+    );
+}
+
+#[test]
+fn literal_lex_errors_test() {
+    let mut lex = Lexer::from(String::from("'ab' \"\\q\" ''\nr\"open"));
+    lex.by_ref().for_each(drop);
+    let errors: Vec<(usize, usize, &str)> = lex
+        .errors()
+        .iter()
+        .map(|e| (e.position.line, e.position.column, e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (1, 1, "character literal must hold exactly one character"),
+            (1, 6, "unknown escape sequence in string literal"),
+            (1, 11, "empty character literal"),
+            (2, 1, "unterminated raw string literal"),
+        ]
+    );
+}
+
+#[test]
+fn shebang_line_is_counted_test() {
+    let mut lex = Lexer::from(String::from("#!/usr/bin/env monkey\nlet @"));
+    lex.by_ref().for_each(drop);
+    assert_eq!(lex.errors()[0].position, Position { line: 2, column: 5 });
+}
+
+#[test]
+fn interned_identifiers_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("i + j * i")).collect();
+    match (&tokens[0], &tokens[2], &tokens[4]) {
+        (Token::Identifier(first), Token::Identifier(other), Token::Identifier(again)) => {
+            assert!(Rc::ptr_eq(first, again));
+            assert!(!Rc::ptr_eq(first, other));
+        }
+        _ => panic!("expected identifiers, got {:?}", tokens),
+    }
+}
+
+#[test]
+fn lexer_stays_finished_test() {
+    let mut lex = Lexer::from(String::from("let x"));
+    assert_eq!(lex.next(), Some(Token::Let));
+    assert_eq!(lex.next(), Some(Token::Identifier("x".into())));
+    for _ in 0..5 {
+        assert_eq!(lex.next(), None);
+    }
+
+    let mut lex = Lexer::from(String::from("  "));
+    for _ in 0..5 {
+        assert_eq!(lex.next(), None);
+    }
+}
+
+#[test]
+fn token_position_test() {
+    let mut lex = Lexer::from(String::from("let x =\n  \"a\nb\" + 1;"));
+    let mut positions = vec![];
+    while lex.next().is_some() {
+        let p = lex.token_position();
+        positions.push((p.line, p.column));
+    }
+    assert_eq!(
+        positions,
+        vec![(1, 1), (1, 5), (1, 7), (2, 3), (3, 4), (3, 6), (3, 7)]
+    );
+    assert_eq!(lex.token_position(), Position { line: 3, column: 8 });
+}
+
+#[test]
+fn token_span_test() {
+    let mut lex = Lexer::from(String::from("let x ==\n  \"a\nb\""));
+    let mut spans = vec![];
+    while lex.next().is_some() {
+        let Span { start, end } = lex.token_span();
+        spans.push(((start.line, start.column), (end.line, end.column)));
+    }
+    assert_eq!(
+        spans,
+        vec![
+            ((1, 1), (1, 4)),
+            ((1, 5), (1, 6)),
+            ((1, 7), (1, 9)),
+            ((2, 3), (3, 3)),
+        ]
+    );
+    let end = Position { line: 3, column: 3 };
+    assert_eq!(lex.token_span(), Span { start: end, end });
+}
+
+#[test]
+fn macro_keyword_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("macro(x, y) { x + y; }; macros")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Macro,
+            Token::Limiter(LimiterToken::LParen),
+            Token::Identifier("x".into()),
+            Token::Limiter(LimiterToken::Comma),
+            Token::Identifier("y".into()),
+            Token::Limiter(LimiterToken::RParen),
+            Token::Limiter(LimiterToken::LBrace),
+            Token::Identifier("x".into()),
+            Token::Plus,
+            Token::Identifier("y".into()),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Limiter(LimiterToken::RBrace),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("macros".into()),
+        ]
+    );
+}
+
+#[test]
+fn loop_keywords_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("while (x) {}; whiles for")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::While,
+            Token::Limiter(LimiterToken::LParen),
+            Token::Identifier("x".into()),
+            Token::Limiter(LimiterToken::RParen),
+            Token::Limiter(LimiterToken::LBrace),
+            Token::Limiter(LimiterToken::RBrace),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("whiles".into()),
+            Token::For,
+        ]
+    );
+}
+
+#[test]
+fn non_ascii_digits_test() {
+    // `²` is neither part of an identifier nor a number, and nothing after it gets lost
+    let tokens: Vec<Token> = Lexer::from(String::from("x²=3; x2 = 1")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("x".into()),
+            Token::Illegal(String::from("²")),
+            Token::Assign,
+            Token::Literal(String::from("3")),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("x2".into()),
+            Token::Assign,
+            Token::Literal(String::from("1")),
+        ]
+    );
+}
+
+#[test]
+fn null_keyword_test() {
+    let tokens: Vec<Token> = Lexer::from(String::from("x == null; nullable")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("x".into()),
+            Token::EQ,
+            Token::Null,
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("nullable".into()),
+        ]
+    );
+    assert_eq!(Token::Null.to_string(), "null");
+}
+
+#[test]
+fn trivia_test() {
+    let input = "#!/usr/bin/env monkey
+let five = 5; // five
+  // on its own line\t
+let s = \"a\\n\\\"b\" + '\\'';\r\n\t@ x == y
+";
+    let tokens: Vec<Token> = Lexer::with_trivia(String::from(input)).collect();
+    assert_eq!(
+        tokens[..6],
+        [
+            Token::Comment(String::from("#!/usr/bin/env monkey")),
+            Token::Whitespace(String::from("\n")),
+            Token::Let,
+            Token::Whitespace(String::from(" ")),
+            Token::Identifier("five".into()),
+            Token::Whitespace(String::from(" ")),
+        ]
+    );
+    assert!(tokens.contains(&Token::Comment(String::from("// five"))));
+    assert!(tokens.contains(&Token::Comment(String::from("// on its own line\t"))));
+
+    let text: String = tokens.iter().map(|t| t.to_string()).collect();
+    assert_eq!(text, input);
+
+    // the default mode is unchanged
+    let tokens: Vec<Token> = Lexer::from(String::from(input)).collect();
+    assert!(!tokens
+        .iter()
+        .any(|t| matches!(t, Token::Whitespace(_) | Token::Comment(_))));
+}
+
+#[test]
+fn trivia_lone_hash_test() {
+    // only `#!` starts a shebang, a lone `#` is as illegal as anywhere else
+    let tokens: Vec<Token> = Lexer::with_trivia(String::from("# x")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Illegal(String::from("#")),
+            Token::Whitespace(String::from(" ")),
+            Token::Identifier("x".into()),
+        ]
+    );
+}
+
+#[test]
+fn token_display_test() {
+    let input = "let add = fn(x, y) { x ** y != [1][0] && {\"k\": 'v'} };";
+    let text: Vec<String> = Lexer::from(String::from(input))
+        .map(|t| t.to_string())
+        .collect();
+    assert_eq!(
+        text.join(" "),
+        "let add = fn ( x , y ) { x ** y != [ 1 ] [ 0 ] && { \"k\" : 'v' } } ;"
+    );
+}
+
+#[test]
+fn tokenize_test() {
+    assert_eq!(
+        tokenize("let x = 5;"),
+        Ok(vec![
+            Token::Let,
+            Token::Identifier("x".into()),
+            Token::Assign,
+            Token::Literal(String::from("5")),
+            Token::Limiter(LimiterToken::Semicolon),
+        ])
+    );
+    assert_eq!(tokenize(""), Ok(vec![]));
+    assert_eq!(
+        tokenize("let x = 5 ? 2;"),
+        Err(vec![LexError {
+            position: Position {
+                line: 1,
+                column: 11
+            },
+            found: '?',
+            message: String::from("unexpected character '?'"),
+        }])
+    );
+}
+
+#[test]
+fn identifiers_with_digits_test() {
+    let tokens: Vec<Token> =
+        Lexer::from(String::from("let foo1 = x1 + _private2; let2ms fn1 2let")).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier("foo1".into()),
+            Token::Assign,
+            Token::Identifier("x1".into()),
+            Token::Plus,
+            Token::Identifier("_private2".into()),
+            Token::Limiter(LimiterToken::Semicolon),
+            Token::Identifier("let2ms".into()),
+            Token::Identifier("fn1".into()),
+            Token::Literal(String::from("2")),
+            Token::Let,
+        ]
+    );
+}
@@ -0,0 +1,43 @@
+//! An interpreter for the Monkey language.
+//!
+//! Source goes through the [`lexer`] into tokens, the [`parser`] into a [`Program`] and the
+//! [`evaluator`] into an [`object::Object`]:
+//!
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! use monkey_rs::environment::Environment;
+//! use monkey_rs::evaluator::eval_program;
+//!
+//! let program = monkey_rs::parse("let add = fn(a, b) { a + b }; add(1, 2)").unwrap();
+//! let mut env = Rc::new(RefCell::new(Environment::new()));
+//! assert_eq!(eval_program(&program, &mut env).to_string(), "3");
+//! ```
+
+pub mod ast;
+mod builtins;
+pub mod environment;
+pub mod evaluator;
+pub mod lexer;
+pub mod object;
+pub mod parser;
+
+pub use ast::Program;
+pub use parser::{ParseError, Parser};
+
+/// Parses a whole program, giving every error found if there's any.
+///
+/// ```
+/// let program = monkey_rs::parse("let x = 1 + 2 * 3;").unwrap();
+/// assert_eq!(program.to_string(), "let x = (1 + (2 * 3));");
+///
+/// let errors = monkey_rs::parse("let = 5;").unwrap_err();
+/// assert_eq!(
+///     errors[0].to_string(),
+///     "expected next token to be an identifier, got `=` instead at line 1, column 5"
+/// );
+/// ```
+pub fn parse(source: &str) -> Result<Program, Vec<ParseError>> {
+    Parser::from_source(source).parse_program()
+}
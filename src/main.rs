@@ -1,8 +1,54 @@
-mod lexer;
+use std::env;
+use std::io::{stdin, IsTerminal};
+use std::process::ExitCode;
+use std::thread;
+
+mod color;
 mod repl;
-mod parser;
-mod ast;
 
-fn main() {
-    let _ = repl::start();
+use repl::ReplMode;
+
+/// Enough for programs to recurse as deep as the evaluator allows, with room to spare.
+const STACK_SIZE: usize = 32 << 20;
+
+/// Programs are run on a thread of their own, as the main thread's stack is too small for them.
+fn main() -> ExitCode {
+    let runner = thread::Builder::new().stack_size(STACK_SIZE).spawn(run);
+    match runner.map(|runner| runner.join()) {
+        Ok(Ok(code)) => code,
+        Ok(Err(_)) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("can't start the interpreter: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mode = match args.first().map(String::as_str) {
+        Some("--tokens") => ReplMode::Tokens,
+        Some("--parse") => ReplMode::Parse,
+        _ => ReplMode::Eval,
+    };
+    if mode != ReplMode::Eval {
+        args.remove(0);
+    }
+
+    match args.as_slice() {
+        // a script piped in is run as a whole rather than line by line
+        [] if !stdin().is_terminal() => repl::run_stdin(mode),
+        [] => match repl::start(mode) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("repl failed: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        [command, path] if command == "run" => repl::run_file(path, mode),
+        _ => {
+            eprintln!("usage: monkey [--tokens | --parse] [run <file.mk>]");
+            ExitCode::from(2)
+        }
+    }
 }
@@ -0,0 +1,200 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::ast::{Spanned, Statement};
+use crate::environment::Environment;
+
+/// Called with the arguments and how many more function calls can be in progress, which the
+/// builtins that call functions themselves pass on to `apply_function`.
+pub type BuiltinFunction = fn(Vec<Object>, usize) -> Object;
+
+/// A function provided by the interpreter rather than written in Monkey.
+#[derive(Clone, Copy, Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub function: BuiltinFunction,
+}
+
+/// Builtins are told apart by name, function pointers can't be compared reliably.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+/// The values Monkey programs evaluate to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Object {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    Array(Vec<Object>),
+    Hash(HashMap<HashKey, HashPair>),
+    Null,
+    /// The value of a `return` on its way up to the function call or program it leaves.
+    ReturnValue(Box<Object>),
+    Error(String),
+    Function {
+        parameters: Vec<String>,
+        /// Shared by the copies of the function, which tells them apart from other functions.
+        body: Rc<Spanned<Statement>>,
+        /// Where the function was defined, so its body sees the names bound there.
+        env: Rc<RefCell<Environment>>,
+    },
+    Builtin(Builtin),
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) => "INTEGER",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::String(_) => "STRING",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
+            Object::Null => "NULL",
+            Object::ReturnValue(_) => "RETURN_VALUE",
+            Object::Error(_) => "ERROR",
+            Object::Function { .. } => "FUNCTION",
+            Object::Builtin(_) => "BUILTIN",
+        }
+    }
+
+    /// Whether the value counts as true in a condition: everything but `false` and `null` does.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Boolean(b) => *b,
+            Object::Null => false,
+            Object::ReturnValue(value) => value.is_truthy(),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::Integer(n) => write!(f, "{}", n),
+            Object::Boolean(b) => write!(f, "{}", b),
+            Object::String(s) => write!(f, "{}", s),
+            Object::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Object::Hash(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .values()
+                    .map(|pair| format!("{}: {}", pair.key, pair.value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Object::Null => write!(f, "null"),
+            Object::ReturnValue(value) => write!(f, "{}", value),
+            Object::Error(message) => write!(f, "{}", message),
+            Object::Function {
+                parameters, body, ..
+            } => write!(f, "fn({}) {}", parameters.join(", "), body),
+            Object::Builtin(builtin) => write!(f, "builtin function {}", builtin.name),
+        }
+    }
+}
+
+/// What a value is filed under in a hash. Values of different types never share a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HashKey {
+    type_name: &'static str,
+    value: u64,
+}
+
+/// A hash entry, keeping the original key around to show it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+pub trait Hashable {
+    /// The key to file the value under in a hash, or `None` if it can't be used as one.
+    fn hash_key(&self) -> Option<HashKey>;
+}
+
+impl Hashable for Object {
+    fn hash_key(&self) -> Option<HashKey> {
+        let value = match self {
+            Object::Integer(n) => *n as u64,
+            Object::Boolean(b) => *b as u64,
+            Object::String(s) => {
+                let mut hasher = DefaultHasher::new();
+                s.hash(&mut hasher);
+                hasher.finish()
+            }
+            _ => return None,
+        };
+
+        Some(HashKey {
+            type_name: self.type_name(),
+            value,
+        })
+    }
+}
+
+#[test]
+fn hash_key_test() {
+    let hello = Object::String(String::from("hello"));
+    assert_eq!(hello.hash_key(), hello.clone().hash_key());
+    assert_ne!(
+        hello.hash_key(),
+        Object::String(String::from("world")).hash_key()
+    );
+    assert_eq!(Object::Integer(1).hash_key(), Object::Integer(1).hash_key());
+    assert_ne!(
+        Object::Integer(1).hash_key(),
+        Object::Boolean(true).hash_key()
+    );
+    assert_eq!(Object::Array(vec![]).hash_key(), None);
+    assert_eq!(Object::Null.hash_key(), None);
+}
+
+#[test]
+fn is_truthy_test() {
+    let tests = [
+        (Object::Integer(0), true),
+        (Object::Integer(5), true),
+        (Object::Boolean(true), true),
+        (Object::Boolean(false), false),
+        (Object::Null, false),
+        (Object::ReturnValue(Box::new(Object::Null)), false),
+    ];
+
+    for (object, expected) in tests {
+        assert_eq!(object.is_truthy(), expected, "{:?}", object);
+    }
+}
+
+#[test]
+fn display_test() {
+    let tests = [
+        (Object::Integer(-5), "-5"),
+        (Object::Boolean(true), "true"),
+        (Object::String(String::from("a \"b\"")), "a \"b\""),
+        (Object::Null, "null"),
+        (
+            Object::Array(vec![Object::Integer(1), Object::Boolean(false)]),
+            "[1, false]",
+        ),
+        (Object::Array(vec![]), "[]"),
+        (Object::ReturnValue(Box::new(Object::Integer(1))), "1"),
+        (
+            Object::Error(String::from("division by zero")),
+            "division by zero",
+        ),
+    ];
+
+    for (object, expected) in tests {
+        assert_eq!(object.to_string(), expected);
+    }
+}
@@ -1,23 +1,165 @@
+use std::fmt;
 use std::mem::take;
 
-use crate::ast::{Expression, Program, Statement};
-use crate::lexer::{Lexer, LimiterToken, Token};
+use crate::ast::{Expression, Program, Spanned, Statement};
+use crate::lexer::{LexError, Lexer, LimiterToken, Position, Span, Token};
 
-struct Parser {
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The grammar needed `expected` next. `found` is `None` when the input ended instead.
+    UnexpectedToken {
+        expected: Token,
+        found: Option<Token>,
+        position: Span,
+    },
+    ExpectedIdentifier {
+        found: Option<Token>,
+        position: Span,
+    },
+    /// A token that can't start an expression was found where one was expected.
+    NoPrefixParseFn { token: Token, position: Span },
+    /// The input ended where an expression was expected.
+    UnexpectedEof { position: Span },
+    /// An integer literal too big to fit in an `i64`.
+    IntegerOverflow { literal: String, position: Span },
+    /// A for loop header without exactly three clauses, `position` being where that showed.
+    ForClauses { position: Span },
+    /// Something other than an identifier or an index expression on the left of `=`.
+    InvalidAssignmentTarget { target: String, position: Span },
+    /// Expressions or blocks nested more than `limit` deep, which could otherwise overflow the
+    /// stack.
+    NestingTooDeep { limit: usize, position: Span },
+    /// Problems found by the lexer, reported with the parser's so nothing gets lost.
+    Lex(LexError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                expected,
+                found,
+                position,
+            } => write!(
+                f,
+                "expected next token to be `{}`, got {} instead at {}",
+                expected,
+                describe(found),
+                position.start
+            ),
+            ParseError::ExpectedIdentifier { found, position } => write!(
+                f,
+                "expected next token to be an identifier, got {} instead at {}",
+                describe(found),
+                position.start
+            ),
+            ParseError::NoPrefixParseFn { token, position } => write!(
+                f,
+                "unexpected token `{}` at start of expression at {}",
+                token, position.start
+            ),
+            ParseError::UnexpectedEof { position } => {
+                write!(f, "unexpected end of input at {}", position.start)
+            }
+            ParseError::IntegerOverflow { literal, position } => write!(
+                f,
+                "integer literal {} is too big at {}",
+                literal, position.start
+            ),
+            ParseError::ForClauses { position } => write!(
+                f,
+                "expected 3 clauses separated by `;` in for loop header at {}",
+                position.start
+            ),
+            ParseError::InvalidAssignmentTarget { target, position } => {
+                write!(f, "can't assign to `{}` at {}", target, position.start)
+            }
+            ParseError::NestingTooDeep { limit, position } => write!(
+                f,
+                "nesting deeper than {} levels at {}",
+                limit, position.start
+            ),
+            ParseError::Lex(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Lex(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn describe(found: &Option<Token>) -> String {
+    match found {
+        Some(t) => format!("`{}`", t),
+        None => String::from("end of input"),
+    }
+}
+
+/// How deep expressions and blocks can nest unless set otherwise with `Parser::with_max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+pub struct Parser {
     lexer: Lexer,
     curr_token: Option<Token>,
     peek_token: Option<Token>,
+    curr_span: Span,
+    peek_span: Span,
+    errors: Vec<ParseError>,
+    /// How many of `errors` the iterator has handed out.
+    errors_reported: usize,
+    /// Whether the current token ends the statement last handed out, so the iterator has to move
+    /// past it before parsing the next. Moving on only then keeps the lexer from reading ahead
+    /// of what's been asked for.
+    at_statement_end: bool,
+    /// How many expressions and blocks are being parsed inside one another.
+    depth: usize,
+    max_depth: usize,
+    /// Set once nesting went past `max_depth`, after which nothing more is parsed.
+    too_deep: bool,
 }
 
-#[derive(PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
 enum Precedence {
     Lowest,
+    Assign,
     Equals,
     LessGreater,
     Sum,
     Product,
     Prefix,
+    /// Above prefix operators so `-2 ** 2` is `-(2 ** 2)`.
+    Power,
     Call,
+    Index,
+}
+
+impl Precedence {
+    /// The precedence just below this one.
+    fn lower(self) -> Self {
+        match self {
+            Precedence::Lowest | Precedence::Assign => Precedence::Lowest,
+            Precedence::Equals => Precedence::Assign,
+            Precedence::LessGreater => Precedence::Equals,
+            Precedence::Sum => Precedence::LessGreater,
+            Precedence::Product => Precedence::Sum,
+            Precedence::Prefix => Precedence::Product,
+            Precedence::Power => Precedence::Prefix,
+            Precedence::Call => Precedence::Power,
+            Precedence::Index => Precedence::Call,
+        }
+    }
+}
+
+/// Which way a chain of the same infix operator groups.
+#[derive(Debug, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
 }
 
 impl Parser {
@@ -26,38 +168,137 @@ impl Parser {
             lexer,
             curr_token: None,
             peek_token: None,
+            curr_span: Span::default(),
+            peek_span: Span::default(),
+            errors: vec![],
+            errors_reported: 0,
+            at_statement_end: false,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            too_deep: false,
         };
         p.next_token();
         p.next_token();
 
-        return p;
+        p
     }
 
-    pub fn parse_program(&mut self) -> Program {
-        let mut prog = Program::new();
+    /// A parser reading straight from `source`.
+    ///
+    /// ```
+    /// use monkey_rs::Parser;
+    ///
+    /// let mut statements = Parser::from_source("let a = 1; a + 1;");
+    /// assert_eq!(statements.next().unwrap().unwrap().to_string(), "let a = 1;");
+    /// assert_eq!(statements.next().unwrap().unwrap().to_string(), "(a + 1);");
+    /// assert!(statements.next().is_none());
+    /// ```
+    pub fn from_source(source: &str) -> Self {
+        Self::new(Lexer::from(String::from(source)))
+    }
 
-        while self.curr_token.is_some() {
-            let stmt = self.parse_statement();
-            if let Some(s) = stmt {
-                prog.statements.push(s);
+    /// Limits how deep expressions and blocks can nest before parsing stops with
+    /// `ParseError::NestingTooDeep`.
+    ///
+    /// ```
+    /// use monkey_rs::{ParseError, Parser};
+    ///
+    /// let errors = Parser::from_source("((1))").with_max_depth(2).parse_program().unwrap_err();
+    /// assert!(matches!(errors[..], [ParseError::NestingTooDeep { limit: 2, .. }]));
+    /// ```
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Parses the whole input, giving back the program only if there was nothing wrong with it.
+    pub fn parse_program(&mut self) -> Result<Program, Vec<ParseError>> {
+        let mut prog = Program::new();
+        let mut errors = vec![];
+        for result in self.by_ref() {
+            match result {
+                Ok(statement) => prog.statements.push(statement),
+                Err(error) => errors.push(error),
             }
-            self.next_token();
         }
 
-        return prog;
+        if errors.is_empty() {
+            Ok(prog)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parses as much of the input as it can, skipping over the statements with errors and
+    /// leaving the errors in `errors`.
+    #[cfg(test)]
+    fn parse_statements(&mut self) -> Program {
+        Program {
+            statements: self.by_ref().filter_map(Result::ok).collect(),
+        }
+    }
+
+    /// Skips what's left of a statement that failed to parse, so one mistake doesn't cascade into
+    /// more. It stops on the statement's `;`, on a `}` likely closing the block it was in, or just
+    /// before a `let`, `return`, `if` or `fn` likely starting the next statement.
+    fn synchronize(&mut self) {
+        while self.curr_token.is_some()
+            && !self.curr_token_is(Token::Limiter(LimiterToken::Semicolon))
+            && !self.curr_token_is(Token::Limiter(LimiterToken::RBrace))
+            && !self.peek_token_is(Token::Let)
+            && !self.peek_token_is(Token::Return)
+            && !self.peek_token_is(Token::If)
+            && !self.peek_token_is(Token::While)
+            && !self.peek_token_is(Token::For)
+            && !self.peek_token_is(Token::Function)
+        {
+            self.next_token();
+        }
     }
 
-    fn parse_statement(&mut self) -> Option<Statement> {
-        return match &self.curr_token {
+    fn parse_statement(&mut self) -> Option<Spanned<Statement>> {
+        let start = self.curr_span.start;
+        let statement = match &self.curr_token {
             Some(Token::Let) => self.parse_let_statement(),
             Some(Token::Return) => self.parse_return_statement(),
+            Some(Token::While) => self.parse_while_statement(),
+            Some(Token::For) => self.parse_for_statement(),
+            // a name after `fn` rather than the `(` of a function literal
+            Some(Token::Function) if matches!(self.peek_token, Some(Token::Identifier(_))) => {
+                self.parse_function_statement()
+            }
+            // an empty statement
+            Some(Token::Limiter(LimiterToken::Semicolon)) => None,
             _ => self.parse_expression_statement(),
+        }?;
+
+        Some(self.spanned(start, statement))
+    }
+
+    /// Wraps a node that started at `start` and ends with the current token.
+    fn spanned<T>(&self, start: Position, node: T) -> Spanned<T> {
+        let span = Span {
+            start,
+            end: self.curr_span.end,
         };
+        Spanned::new(node, span)
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
     }
 
     fn next_token(&mut self) {
         self.curr_token = take(&mut self.peek_token);
+        self.curr_span = self.peek_span;
         self.peek_token = self.lexer.next();
+        self.peek_span = self.lexer.token_span();
+
+        if let Some(Token::Illegal(_)) = self.peek_token {
+            if let Some(e) = self.lexer.errors().last() {
+                self.errors.push(ParseError::Lex(e.clone()));
+            }
+        }
     }
 
     fn curr_token_is(&self, other: Token) -> bool {
@@ -74,100 +315,792 @@ impl Parser {
         }
     }
 
-    fn expect_peek(&mut self, other: Token) {
-        match &self.peek_token {
-            Some(t) => {
-                assert_eq!(*t, other);
-                self.next_token()
-            }
-            None => (),
+    /// Moves to the next token if it's the `other` one, otherwise leaves the tokens untouched and
+    /// returns the error to report.
+    fn expect_peek(&mut self, other: Token) -> Result<(), ParseError> {
+        if self.peek_token_is(other.clone()) {
+            self.next_token();
+            Ok(())
+        } else {
+            Err(self.peek_error(other))
         }
     }
 
+    /// `expect_peek` for the parse functions: the error is recorded and `None` tells the caller to
+    /// give up on the node it was parsing.
+    fn expect(&mut self, other: Token) -> Option<()> {
+        self.expect_peek(other)
+            .map_err(|e| self.peek_failed(e))
+            .ok()
+    }
+
+    /// Records an error about the peek token, unless it's an illegal one: the lexer error for it
+    /// is already recorded, and a second one for the same character would only be noise.
+    fn peek_failed(&mut self, error: ParseError) {
+        if !matches!(self.peek_token, Some(Token::Illegal(_))) {
+            self.errors.push(error);
+        }
+    }
+
+    fn peek_error(&self, expected: Token) -> ParseError {
+        ParseError::UnexpectedToken {
+            expected,
+            found: self.peek_token.clone(),
+            position: self.peek_span,
+        }
+    }
+
+    fn peek_identifier_error(&self) -> ParseError {
+        ParseError::ExpectedIdentifier {
+            found: self.peek_token.clone(),
+            position: self.peek_span,
+        }
+    }
+
+    /// A `;` or `}` right after the `return` means there's no value.
     fn parse_return_statement(&mut self) -> Option<Statement> {
-        while !self.curr_token_is(Token::Limiter(LimiterToken::Semicolon)) {
-            self.next_token();
+        if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon))
+            || self.peek_token_is(Token::Limiter(LimiterToken::RBrace))
+        {
+            self.end_statement()?;
+            return Some(Statement::Return(None));
         }
-        // FIXME: this should be a proper expression
-        let fixme = Expression::Identifier(Token::Assign);
-        return Some(Statement::Return(fixme));
+
+        self.next_token(); // current is the start of the returned expression
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        self.end_statement()?;
+        Some(Statement::Return(Some(value)))
     }
 
     fn parse_let_statement(&mut self) -> Option<Statement> {
-        let identifier: Expression = match &self.peek_token {
-            Some(Token::Identifier(s)) => Expression::Identifier(Token::Identifier(s.clone())),
-            _ => return None,
+        let statement = self.parse_let_binding()?;
+
+        self.end_statement()?;
+        Some(statement)
+    }
+
+    /// The `let x = value` part of a let statement, leaving the current token at the end of the
+    /// value.
+    fn parse_let_binding(&mut self) -> Option<Statement> {
+        let identifier = match &self.peek_token {
+            Some(Token::Identifier(s)) => Spanned::new(
+                Expression::Identifier(Token::Identifier(s.clone())),
+                self.peek_span,
+            ),
+            _ => {
+                let error = self.peek_identifier_error();
+                self.peek_failed(error);
+                return None;
+            }
         };
         self.next_token(); // current is the identifier and peek the equal sign
 
-        self.expect_peek(Token::Assign); // current is the equal sign
+        self.expect(Token::Assign)?; // current is the equal sign
+        self.next_token(); // current is the start of the value
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        Some(Statement::Let(identifier, value))
+    }
+
+    /// Parses `fn name(x) { ... }` into the `let name = fn(x) { ... };` it's short for.
+    fn parse_function_statement(&mut self) -> Option<Statement> {
+        let start = self.curr_span.start;
+        let name = self.expect_identifier()?;
+        let identifier = Spanned::new(
+            Expression::Identifier(Token::Identifier(name.into())),
+            self.curr_span,
+        );
+        let function = self.parse_function_literal()?;
+        let function = self.spanned(start, function);
+        if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            self.next_token();
+        }
+
+        Some(Statement::Let(identifier, function))
+    }
+
+    fn parse_while_statement(&mut self) -> Option<Statement> {
+        self.expect(Token::Limiter(LimiterToken::LParen))?;
+        self.next_token(); // current is the start of the condition
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        self.expect(Token::Limiter(LimiterToken::RParen))?;
+        self.expect(Token::Limiter(LimiterToken::LBrace))?;
+        let body = self.parse_block_statement()?;
+
+        Some(Statement::While {
+            condition,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_for_statement(&mut self) -> Option<Statement> {
+        self.expect(Token::Limiter(LimiterToken::LParen))?;
+
+        let init = if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            None
+        } else {
+            self.next_token();
+            Some(Box::new(self.parse_for_clause()?))
+        };
+        self.expect_for_separator()?;
+
+        let condition = if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            None
+        } else {
+            self.next_token();
+            Some(self.parse_expression(Precedence::Lowest)?)
+        };
+        self.expect_for_separator()?;
+
+        let post = if self.peek_token_is(Token::Limiter(LimiterToken::RParen)) {
+            None
+        } else {
+            self.too_many_for_clauses()?;
+            self.next_token();
+            Some(Box::new(self.parse_for_clause()?))
+        };
+        self.too_many_for_clauses()?;
+        self.expect(Token::Limiter(LimiterToken::RParen))?;
+
+        self.expect(Token::Limiter(LimiterToken::LBrace))?;
+        let body = self.parse_block_statement()?;
+
+        Some(Statement::For {
+            init,
+            condition,
+            post,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_for_clause(&mut self) -> Option<Spanned<Statement>> {
+        let start = self.curr_span.start;
+        let clause = match self.curr_token {
+            Some(Token::Let) => self.parse_let_binding()?,
+            _ => Statement::Expression(self.parse_expression(Precedence::Lowest)?.node),
+        };
+
+        Some(self.spanned(start, clause))
+    }
+
+    /// Moves onto the `;` after the first or second clause of a for loop header, which closing
+    /// the header early would leave out.
+    fn expect_for_separator(&mut self) -> Option<()> {
+        if self.peek_token_is(Token::Limiter(LimiterToken::RParen)) {
+            self.for_clauses_error();
+            return None;
+        }
+        self.expect(Token::Limiter(LimiterToken::Semicolon))
+    }
+
+    /// Errors on a `;` where the for loop header should have ended.
+    fn too_many_for_clauses(&mut self) -> Option<()> {
+        if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            self.for_clauses_error();
+            return None;
+        }
+        Some(())
+    }
+
+    /// Records a header with the wrong number of clauses and skips the rest of the loop, so the
+    /// `;` left in the header and the body's `}` aren't taken for the ends of statements while
+    /// recovering. Errors in the body are still reported.
+    fn for_clauses_error(&mut self) {
+        self.errors.push(ParseError::ForClauses {
+            position: self.peek_span,
+        });
 
-        while !self.curr_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+        let mut depth = 0;
+        while let Some(token) = &self.peek_token {
+            match token {
+                Token::Limiter(LimiterToken::LParen) => depth += 1,
+                Token::Limiter(LimiterToken::RParen) if depth == 0 => {
+                    self.next_token();
+                    if self.peek_token_is(Token::Limiter(LimiterToken::LBrace)) {
+                        self.next_token();
+                        self.parse_block_statement();
+                    }
+                    return;
+                }
+                Token::Limiter(LimiterToken::RParen) => depth -= 1,
+                _ => (),
+            }
             self.next_token();
         }
+    }
 
-        // FIXME: this should be a proper expression
-        let fixme = Expression::Identifier(Token::Assign);
-        return Some(Statement::Let(identifier, fixme));
+    /// Moves onto the `;` ending a `let` or `return`. It can be left out before another token,
+    /// but not at the end of the input, where it's most likely a statement cut short.
+    fn end_statement(&mut self) -> Option<()> {
+        if self.peek_token.is_none() {
+            let error = self.peek_error(Token::Limiter(LimiterToken::Semicolon));
+            self.errors.push(error);
+            return None;
+        }
+        if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            self.next_token();
+        }
+        Some(())
     }
 
+    /// The `;` after an expression is optional, even at the end of the input, so the REPL can
+    /// take `5 + 5` and blocks can end in a bare value.
     fn parse_expression_statement(&mut self) -> Option<Statement> {
-        // we know a token exists at the moment since we are in the middle of parsing a statment
-        let express = self.parse_expression(Precedence::Lowest);
-        return match express {
-            Some(e) => Some(Statement::Expression(e)),
-            None => None,
+        let e = self.parse_expression(Precedence::Lowest)?;
+
+        if self.peek_token_is(Token::Limiter(LimiterToken::Semicolon)) {
+            self.next_token();
+        }
+        Some(Statement::Expression(e.node))
+    }
+
+    /// Runs `parse` a level deeper, failing once that's past `max_depth` as the parser recurses
+    /// at each level and would run out of stack on deep enough input. Giving up on the rest of
+    /// the input then saves recovering into the same problem at every level.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= self.max_depth {
+            self.too_deep = true;
+            self.errors.push(ParseError::NestingTooDeep {
+                limit: self.max_depth,
+                position: self.curr_span,
+            });
+            return None;
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Spanned<Expression>> {
+        self.nested(|p| p.parse_expression_inner(precedence))
+    }
+
+    fn parse_expression_inner(&mut self, precedence: Precedence) -> Option<Spanned<Expression>> {
+        if self.curr_token.is_none() {
+            self.errors.push(ParseError::UnexpectedEof {
+                position: self.curr_span,
+            });
+            return None;
+        }
+
+        let start = self.curr_span.start;
+        let left = match self.curr_token.as_ref()? {
+            Token::If => self.parse_if_expression(),
+            Token::Function => self.parse_function_literal(),
+            Token::Macro => self.parse_macro_literal(),
+            Token::Limiter(LimiterToken::LBracket) => self.parse_array_literal(),
+            Token::Limiter(LimiterToken::LBrace) => self.parse_hash_literal(),
+            Token::Bang | Token::Minus => self.parse_prefix_expression(),
+            Token::Limiter(LimiterToken::LParen) => self.parse_grouped_expression(),
+            Token::Literal(literal) => {
+                let literal = literal.clone();
+                self.parse_integer_literal(literal)
+            }
+            // the lexer already reported it
+            Token::Illegal(_) => None,
+            token => match prefix_parsing_fn(token) {
+                Some(e) => Some(e),
+                None => {
+                    self.errors.push(ParseError::NoPrefixParseFn {
+                        token: token.clone(),
+                        position: self.curr_span,
+                    });
+                    None
+                }
+            },
+        }?;
+        let mut left = self.spanned(start, left);
+
+        while !self.peek_token_is(Token::Limiter(LimiterToken::Semicolon))
+            && precedence < self.peek_precedence()
+        {
+            // only infix operators have a precedence above the lowest, so the peek token is one
+            let expression = match &self.peek_token {
+                Some(Token::Limiter(LimiterToken::LParen)) => {
+                    self.next_token();
+                    self.parse_call_expression(left)?
+                }
+                Some(Token::Limiter(LimiterToken::LBracket)) => {
+                    self.next_token();
+                    self.parse_index_expression(left)?
+                }
+                Some(Token::Assign) => {
+                    self.next_token();
+                    self.parse_assign_expression(left)?
+                }
+                Some(_) => {
+                    self.next_token();
+                    self.parse_infix_expression(left)?
+                }
+                None => return Some(left),
+            };
+            left = self.spanned(start, expression);
+        }
+
+        Some(left)
+    }
+
+    fn peek_precedence(&self) -> Precedence {
+        match &self.peek_token {
+            Some(t) => precedence_of(t),
+            None => Precedence::Lowest,
+        }
+    }
+
+    fn curr_precedence(&self) -> Precedence {
+        match &self.curr_token {
+            Some(t) => precedence_of(t),
+            None => Precedence::Lowest,
+        }
+    }
+
+    /// The precedence to parse the right operand of the current infix operator at. A right
+    /// associative one goes a level lower so the right operand takes in the next of the same
+    /// operator too.
+    fn right_operand_precedence(&self) -> Precedence {
+        let precedence = self.curr_precedence();
+        match self.curr_token.as_ref().map(associativity) {
+            Some(Associativity::Right) => precedence.lower(),
+            _ => precedence,
+        }
+    }
+
+    fn parse_prefix_expression(&mut self) -> Option<Expression> {
+        let operator = self.curr_token.clone()?;
+        self.next_token(); // current is the start of the operand
+        let right = self.parse_expression(Precedence::Prefix)?;
+
+        Some(Expression::Prefix {
+            operator,
+            right: Box::new(right),
+        })
+    }
+
+    /// Parses the right operand of the operator the current token is on. Binding it at the
+    /// operator's own precedence makes operators of equal precedence group to the left.
+    fn parse_infix_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
+        let operator = self.curr_token.clone()?;
+        let precedence = self.right_operand_precedence();
+        self.next_token(); // current is the start of the right operand
+        let right = self.parse_expression(precedence)?;
+
+        Some(Expression::Infix {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        })
+    }
+
+    /// `=` groups to the right, so `a = b = 3` assigns `b = 3` to `a`.
+    fn parse_assign_expression(&mut self, target: Spanned<Expression>) -> Option<Expression> {
+        if !matches!(
+            *target,
+            Expression::Identifier(_) | Expression::Index { .. }
+        ) {
+            self.errors.push(ParseError::InvalidAssignmentTarget {
+                target: target.to_string(),
+                position: self.curr_span,
+            });
+            return None;
+        }
+
+        let precedence = self.right_operand_precedence();
+        self.next_token(); // current is the start of the value
+        let value = self.parse_expression(precedence)?;
+
+        Some(Expression::Assign {
+            target: Box::new(target),
+            value: Box::new(value),
+        })
+    }
+
+    fn parse_grouped_expression(&mut self) -> Option<Expression> {
+        self.next_token(); // current is the start of the inner expression
+        let e = self.parse_expression(Precedence::Lowest)?;
+        self.expect(Token::Limiter(LimiterToken::RParen))?;
+
+        // the parentheses end up in the span of the expression they're around
+        Some(e.node)
+    }
+
+    fn parse_integer_literal(&mut self, literal: String) -> Option<Expression> {
+        match literal.parse() {
+            Ok(value) => Some(Expression::IntegerLiteral {
+                token: Token::Literal(literal),
+                value,
+            }),
+            Err(_) => {
+                self.errors.push(ParseError::IntegerOverflow {
+                    literal,
+                    position: self.curr_span,
+                });
+                None
+            }
+        }
+    }
+
+    fn parse_if_expression(&mut self) -> Option<Expression> {
+        self.expect(Token::Limiter(LimiterToken::LParen))?;
+        self.next_token(); // current is the start of the condition
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        self.expect(Token::Limiter(LimiterToken::RParen))?;
+        self.expect(Token::Limiter(LimiterToken::LBrace))?;
+        let consequence = self.parse_block_statement()?;
+
+        // an `else if` keeps the chain going with the next if as the whole alternative
+        let alternative = if self.peek_token_is(Token::Else) {
+            self.next_token();
+            if self.peek_token_is(Token::If) {
+                self.next_token();
+                let start = self.curr_span.start;
+                let chained = self.parse_if_expression()?;
+                Some(Box::new(
+                    self.spanned(start, Statement::Expression(chained)),
+                ))
+            } else {
+                self.expect(Token::Limiter(LimiterToken::LBrace))?;
+                Some(Box::new(self.parse_block_statement()?))
+            }
+        } else {
+            None
+        };
+
+        Some(Expression::If {
+            condition: Box::new(condition),
+            consequence: Box::new(consequence),
+            alternative,
+        })
+    }
+
+    fn parse_function_literal(&mut self) -> Option<Expression> {
+        let (parameters, body) = self.parse_parameters_and_body()?;
+        Some(Expression::FunctionLiteral {
+            parameters,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_macro_literal(&mut self) -> Option<Expression> {
+        let (parameters, body) = self.parse_parameters_and_body()?;
+        Some(Expression::MacroLiteral {
+            parameters,
+            body: Box::new(body),
+        })
+    }
+
+    /// Parses the `(x, y) { ... }` after the `fn` or `macro` keyword the current token is on.
+    fn parse_parameters_and_body(&mut self) -> Option<(Vec<String>, Spanned<Statement>)> {
+        self.expect(Token::Limiter(LimiterToken::LParen))?;
+        let parameters = self.parse_function_parameters()?;
+        self.expect(Token::Limiter(LimiterToken::LBrace))?;
+        let body = self.parse_block_statement()?;
+
+        Some((parameters, body))
+    }
+
+    /// Parses a comma separated list of parameter names, starting with the current token on the
+    /// `(` and leaving it on the `)`.
+    fn parse_function_parameters(&mut self) -> Option<Vec<String>> {
+        let mut parameters = vec![];
+        if self.peek_token_is(Token::Limiter(LimiterToken::RParen)) {
+            self.next_token();
+            return Some(parameters);
+        }
+
+        parameters.push(self.expect_identifier()?);
+        while self.peek_token_is(Token::Limiter(LimiterToken::Comma)) {
+            self.next_token();
+            parameters.push(self.expect_identifier()?);
+        }
+        self.expect(Token::Limiter(LimiterToken::RParen))?;
+
+        Some(parameters)
+    }
+
+    fn parse_call_expression(&mut self, function: Spanned<Expression>) -> Option<Expression> {
+        let arguments = self.parse_expression_list(Token::Limiter(LimiterToken::RParen))?;
+        Some(Expression::Call {
+            function: Box::new(function),
+            arguments,
+        })
+    }
+
+    fn parse_index_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
+        self.next_token();
+        let index = self.parse_expression(Precedence::Lowest)?;
+        self.expect(Token::Limiter(LimiterToken::RBracket))?;
+
+        Some(Expression::Index {
+            left: Box::new(left),
+            index: Box::new(index),
+        })
+    }
+
+    fn parse_array_literal(&mut self) -> Option<Expression> {
+        let elements = self.parse_expression_list(Token::Limiter(LimiterToken::RBracket))?;
+        Some(Expression::ArrayLiteral(elements))
+    }
+
+    /// A `{` only starts a block where the grammar asks for one (after `if`, `fn`, ...), so one
+    /// found where an expression is expected is always a hash.
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let mut pairs = vec![];
+        while !self.peek_token_is(Token::Limiter(LimiterToken::RBrace)) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
+            self.expect(Token::Limiter(LimiterToken::Colon))?;
+            self.next_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+
+            // anything but a comma has to be the closing brace, which is checked below
+            if !self.peek_token_is(Token::Limiter(LimiterToken::Comma)) {
+                break;
+            }
+            self.next_token();
+        }
+        self.expect(Token::Limiter(LimiterToken::RBrace))?;
+
+        Some(Expression::HashLiteral(pairs))
+    }
+
+    /// Parses a comma separated list of expressions, starting with the current token on the
+    /// opening delimiter and leaving it on `end`. The last expression can have a comma after it.
+    fn parse_expression_list(&mut self, end: Token) -> Option<Vec<Spanned<Expression>>> {
+        let mut list = vec![];
+        while !self.peek_token_is(end.clone()) {
+            self.next_token();
+            list.push(self.parse_expression(Precedence::Lowest)?);
+
+            // anything but a comma has to be the end, which is checked below
+            if !self.peek_token_is(Token::Limiter(LimiterToken::Comma)) {
+                break;
+            }
+            self.next_token();
+        }
+        self.expect(end)?;
+
+        Some(list)
+    }
+
+    /// Moves to the next token if it's an identifier and returns its name, otherwise records
+    /// the error.
+    fn expect_identifier(&mut self) -> Option<String> {
+        match &self.peek_token {
+            Some(Token::Identifier(s)) => {
+                let name = s.to_string();
+                self.next_token();
+                Some(name)
+            }
+            _ => {
+                let error = self.peek_identifier_error();
+                self.peek_failed(error);
+                None
+            }
         }
+    }
 
+    /// Parses the statements of a block, starting with the current token on the `{` and leaving
+    /// it on the matching `}`. Running out of input before the `}` is an error.
+    fn parse_block_statement(&mut self) -> Option<Spanned<Statement>> {
+        self.nested(Self::parse_block_statement_inner)
     }
 
-    fn parse_expression(&self, precedence: Precedence) -> Option<Expression> {
-        // we know a token exists at the moment since we are in the middle of parsing a statment
-        let token = self.curr_token.as_ref()?;
-        return prefix_parsing_fn(&token);
+    fn parse_block_statement_inner(&mut self) -> Option<Spanned<Statement>> {
+        let start = self.curr_span.start;
+        let mut statements = vec![];
+        self.next_token();
+
+        while self.curr_token.is_some() && !self.curr_token_is(Token::Limiter(LimiterToken::RBrace))
+        {
+            match self.parse_statement() {
+                Some(s) => statements.push(s),
+                None if self.too_deep => return None,
+                None => {
+                    self.synchronize();
+                    if self.curr_token_is(Token::Limiter(LimiterToken::RBrace)) {
+                        break;
+                    }
+                }
+            }
+            self.next_token();
+        }
+
+        if self.curr_token.is_none() {
+            let error = self.peek_error(Token::Limiter(LimiterToken::RBrace));
+            self.errors.push(error);
+            return None;
+        }
+        Some(self.spanned(start, Statement::Block(statements)))
     }
+}
+
+/// Statements are parsed one at a time as they're asked for. After one that doesn't parse the
+/// parser skips to where the next one likely starts, so every error gets its own item before it
+/// carries on, and it's done once the input runs out or it's nested too deep to go on.
+impl Iterator for Parser {
+    type Item = Result<Spanned<Statement>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(error) = self.errors.get(self.errors_reported) {
+                self.errors_reported += 1;
+                return Some(Err(error.clone()));
+            }
+
+            if self.at_statement_end {
+                self.next_token();
+                self.at_statement_end = false;
+            }
+            if self.too_deep {
+                return None;
+            }
+            self.curr_token.as_ref()?;
 
+            let statement = self.parse_statement();
+            self.at_statement_end = true;
+            match statement {
+                Some(statement) => return Some(Ok(statement)),
+                None if self.too_deep => (),
+                // an empty statement leaves nothing to skip
+                None => self.synchronize(),
+            }
+        }
+    }
 }
 
 fn prefix_parsing_fn(token: &Token) -> Option<Expression> {
     match token {
         Token::Identifier(ident) => Some(Expression::Identifier(Token::Identifier(ident.clone()))),
+        Token::StringLiteral(_) => Some(Expression::StringLiteral(token.clone())),
+        Token::True => Some(Expression::Boolean(true)),
+        Token::False => Some(Expression::Boolean(false)),
+        Token::Null => Some(Expression::Null),
         _ => None,
     }
 }
 
-// fn get_infix_parse_fn(token: Token) -> impl Fn(Expression) -> Option<Expression> {
-//     todo!();
-// }
+fn precedence_of(token: &Token) -> Precedence {
+    match token {
+        Token::Assign => Precedence::Assign,
+        Token::EQ | Token::NotEq => Precedence::Equals,
+        Token::LT | Token::GT => Precedence::LessGreater,
+        Token::Plus | Token::Minus => Precedence::Sum,
+        Token::Asterisk | Token::Slash => Precedence::Product,
+        Token::Power => Precedence::Power,
+        Token::Limiter(LimiterToken::LParen) => Precedence::Call,
+        Token::Limiter(LimiterToken::LBracket) => Precedence::Index,
+        _ => Precedence::Lowest,
+    }
+}
+
+fn associativity(token: &Token) -> Associativity {
+    match token {
+        Token::Assign | Token::Power => Associativity::Right,
+        _ => Associativity::Left,
+    }
+}
+
+#[test]
+fn precedence_of_test() {
+    let tests = [
+        (Token::Assign, Precedence::Assign),
+        (Token::EQ, Precedence::Equals),
+        (Token::NotEq, Precedence::Equals),
+        (Token::LT, Precedence::LessGreater),
+        (Token::GT, Precedence::LessGreater),
+        (Token::Plus, Precedence::Sum),
+        (Token::Minus, Precedence::Sum),
+        (Token::Asterisk, Precedence::Product),
+        (Token::Slash, Precedence::Product),
+        (Token::Power, Precedence::Power),
+        (Token::Limiter(LimiterToken::LParen), Precedence::Call),
+        (Token::Limiter(LimiterToken::LBracket), Precedence::Index),
+        (Token::Bang, Precedence::Lowest),
+        (Token::Limiter(LimiterToken::Semicolon), Precedence::Lowest),
+        (Token::Identifier("x".into()), Precedence::Lowest),
+    ];
+
+    for (token, expected) in tests {
+        assert_eq!(precedence_of(&token), expected, "{:?}", token);
+    }
+    assert!(Precedence::Lowest < Precedence::Assign);
+    assert!(Precedence::Assign < Precedence::Equals);
+    assert!(Precedence::Product < Precedence::Prefix);
+    assert!(Precedence::Prefix < Precedence::Power);
+    assert!(Precedence::Call < Precedence::Index);
+}
+
+#[test]
+fn associativity_test() {
+    assert_eq!(associativity(&Token::Assign), Associativity::Right);
+    assert_eq!(associativity(&Token::Power), Associativity::Right);
+    assert_eq!(associativity(&Token::Plus), Associativity::Left);
+    assert_eq!(associativity(&Token::Slash), Associativity::Left);
+
+    assert_eq!(Precedence::Power.lower(), Precedence::Prefix);
+    assert_eq!(Precedence::Assign.lower(), Precedence::Lowest);
+    assert_eq!(Precedence::Lowest.lower(), Precedence::Lowest);
+}
+
+#[test]
+fn peek_and_curr_precedence_test() {
+    let mut pars = Parser::from_source("a * b");
+    assert_eq!(pars.curr_precedence(), Precedence::Lowest);
+    assert_eq!(pars.peek_precedence(), Precedence::Product);
+
+    pars.next_token();
+    assert_eq!(pars.curr_precedence(), Precedence::Product);
+    assert_eq!(pars.peek_precedence(), Precedence::Lowest);
+
+    pars.next_token();
+    assert_eq!(pars.peek_token, None);
+    assert_eq!(pars.peek_precedence(), Precedence::Lowest);
+
+    pars.next_token();
+    assert_eq!(pars.curr_token, None);
+    assert_eq!(pars.curr_precedence(), Precedence::Lowest);
+}
+
+/// Whether the errors from parsing some input are the ones expected.
+#[cfg(test)]
+type ErrorCheck = fn(&[ParseError]) -> bool;
 
 #[test]
 fn let_statement_test() {
     let input = "let five = 5;
     let ten = 10;
     let foobar = 8080;
+    let greeting = \"hello\";
     ";
-    let lex = Lexer::from(String::from(input));
-    let mut pars = Parser::new(lex);
-    let prog = pars.parse_program();
-    assert_eq!(prog.statements.len(), 3);
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 4);
 
-    let tests: [Statement; 3] = [
+    let tests: [Statement; 4] = [
+        Statement::Let(
+            Expression::Identifier(Token::Identifier("five".into())).into(),
+            Expression::integer(5).into(),
+        ),
         Statement::Let(
-            Expression::Identifier(Token::Identifier(String::from("five"))),
-            Expression::Identifier(Token::Assign),
+            Expression::Identifier(Token::Identifier("ten".into())).into(),
+            Expression::integer(10).into(),
         ),
         Statement::Let(
-            Expression::Identifier(Token::Identifier(String::from("ten"))),
-            Expression::Identifier(Token::Assign),
+            Expression::Identifier(Token::Identifier("foobar".into())).into(),
+            Expression::integer(8080).into(),
         ),
         Statement::Let(
-            Expression::Identifier(Token::Identifier(String::from("foobar"))),
-            Expression::Identifier(Token::Assign),
+            Expression::Identifier(Token::Identifier("greeting".into())).into(),
+            Expression::StringLiteral(Token::StringLiteral(String::from("hello"))).into(),
         ),
     ];
     prog.statements
         .into_iter()
-        .zip(tests.into_iter())
+        .zip(tests)
         .map(|(stmt, test_stmt)| {
             assert_eq!(stmt, test_stmt);
         })
@@ -175,47 +1108,2341 @@ fn let_statement_test() {
 }
 
 #[test]
-fn return_statement_test() {
+fn let_statement_expression_value_test() {
+    let input = "let x = 5 * 5;
+    let y = add(1, 2) + 3
+    let z = \"8080\";";
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("x".into())).into(),
+                Expression::Infix {
+                    left: Box::new(Expression::integer(5).into()),
+                    operator: Token::Asterisk,
+                    right: Box::new(Expression::integer(5).into()),
+                }
+                .into(),
+            ),
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("y".into())).into(),
+                Expression::Infix {
+                    left: Box::new(
+                        Expression::Call {
+                            function: Box::new(
+                                Expression::Identifier(Token::Identifier("add".into())).into()
+                            ),
+                            arguments: vec![
+                                Expression::integer(1).into(),
+                                Expression::integer(2).into()
+                            ],
+                        }
+                        .into()
+                    ),
+                    operator: Token::Plus,
+                    right: Box::new(Expression::integer(3).into()),
+                }
+                .into(),
+            ),
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("z".into())).into(),
+                Expression::StringLiteral(Token::StringLiteral(String::from("8080"))).into(),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn return_statement_test() {
     let input = "return 5;
     return 10;
     return 8080;
+    return x + y;
+    return fn(x) { x; }(2);
     ";
-    let lex = Lexer::from(String::from(input));
-    let mut pars = Parser::new(lex);
-    let prog = pars.parse_program();
-    assert_eq!(prog.statements.len(), 3);
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 5);
 
-    let tests: [Statement; 3] = [
-        Statement::Return(Expression::Identifier(Token::Assign)),
-        Statement::Return(Expression::Identifier(Token::Assign)),
-        Statement::Return(Expression::Identifier(Token::Assign)),
+    let tests: [Statement; 5] = [
+        Statement::Return(Some(Expression::integer(5).into())),
+        Statement::Return(Some(Expression::integer(10).into())),
+        Statement::Return(Some(Expression::integer(8080).into())),
+        Statement::Return(Some(
+            Expression::Infix {
+                left: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+                operator: Token::Plus,
+                right: Box::new(Expression::Identifier(Token::Identifier("y".into())).into()),
+            }
+            .into(),
+        )),
+        Statement::Return(Some(
+            Expression::Call {
+                function: Box::new(
+                    Expression::FunctionLiteral {
+                        parameters: vec![String::from("x")],
+                        body: Box::new(
+                            Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                                Token::Identifier("x".into()),
+                            ))
+                            .into()])
+                            .into(),
+                        ),
+                    }
+                    .into(),
+                ),
+                arguments: vec![Expression::integer(2).into()],
+            }
+            .into(),
+        )),
     ];
     prog.statements
         .into_iter()
-        .zip(tests.into_iter())
+        .zip(tests)
         .map(|(stmt, test_stmt)| {
             assert_eq!(stmt, test_stmt);
         })
         .for_each(drop);
 }
 
+#[test]
+fn return_at_end_of_block_test() {
+    let mut pars = Parser::from_source("fn() { return [y] }");
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::FunctionLiteral {
+            parameters: vec![],
+            body: Box::new(
+                Statement::Block(vec![Statement::Return(Some(
+                    Expression::ArrayLiteral(vec![Expression::Identifier(Token::Identifier(
+                        "y".into()
+                    ))
+                    .into()])
+                    .into()
+                ))
+                .into()])
+                .into()
+            ),
+        })]
+    );
+}
+
+#[test]
+fn bare_return_statement_test() {
+    let tests = [
+        ("return;", vec![Statement::Return(None)]),
+        (
+            "return; 5",
+            vec![
+                Statement::Return(None),
+                Statement::Expression(Expression::integer(5)),
+            ],
+        ),
+        (
+            "return 5;",
+            vec![Statement::Return(Some(Expression::integer(5).into()))],
+        ),
+        (
+            "fn() { return; }",
+            vec![Statement::Expression(Expression::FunctionLiteral {
+                parameters: vec![],
+                body: Box::new(Statement::Block(vec![Statement::Return(None).into()]).into()),
+            })],
+        ),
+        (
+            "if (x) { return }",
+            vec![Statement::Expression(Expression::If {
+                condition: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+                consequence: Box::new(
+                    Statement::Block(vec![Statement::Return(None).into()]).into(),
+                ),
+                alternative: None,
+            })],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, expected, "{}", input);
+    }
+}
+
+#[test]
+fn missing_semicolon_at_end_of_input_test() {
+    let tests: [(&str, ErrorCheck); 4] = [
+        ("let x = 5", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::Semicolon),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("return 10", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::Semicolon),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        (
+            "let x = 5;\nlet y =",
+            |errors| matches!(errors, [ParseError::UnexpectedEof { position }] if position.start.line == 2),
+        ),
+        ("let x =\n", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
 #[test]
 fn identifier_expression_test() {
     let input = "foobar;";
 
-    let lex = Lexer::from(String::from(input));
-    let mut pars = Parser::new(lex);
-    let prog = pars.parse_program();
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
     assert_eq!(prog.statements.len(), 1);
 
-    let s = prog.statements[0].clone();
+    let s = prog.statements[0].node.clone();
     assert!(match s {
-        Statement::Expression(e) => {
-            if let Expression::Identifier(Token::Identifier(s)) = e {
-                assert_eq!(s, String::from("foobar"));
-                true
-            } else { false }
-        },
+        Statement::Expression(Expression::Identifier(Token::Identifier(s))) => {
+            assert_eq!(&*s, "foobar");
+            true
+        }
         _ => false,
     });
 }
+
+#[test]
+fn integer_literal_expression_test() {
+    let input = "5; 9223372036854775807;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![
+            Statement::Expression(Expression::integer(5)),
+            Statement::Expression(Expression::integer(i64::MAX)),
+        ]
+    );
+}
+
+#[test]
+fn integer_literal_overflow_test() {
+    let input = "let x = 1;
+    let y = 9223372036854775808;
+    let z = 3;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_statements();
+
+    assert!(matches!(
+        pars.errors(),
+        [ParseError::IntegerOverflow { literal, position }]
+            if literal == "9223372036854775808"
+                && position.start == Position { line: 2, column: 13 }
+                && position.end == Position { line: 2, column: 32 }
+    ));
+    assert_eq!(prog.statements.len(), 2);
+}
+
+#[test]
+fn null_expression_test() {
+    let input = "let x = null; x == null; return null; [null]";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let x = || Expression::Identifier(Token::Identifier("x".into()));
+    assert_eq!(
+        prog.statements,
+        vec![
+            Statement::Let(x().into(), Expression::Null.into()),
+            Statement::Expression(Expression::Infix {
+                left: Box::new(x().into()),
+                operator: Token::EQ,
+                right: Box::new(Expression::Null.into()),
+            }),
+            Statement::Return(Some(Expression::Null.into())),
+            Statement::Expression(Expression::ArrayLiteral(vec![Expression::Null.into()])),
+        ]
+    );
+    assert_eq!(
+        prog.to_string(),
+        "let x = null;\n(x == null);\nreturn null;\n[null];"
+    );
+
+    let prog = crate::parse("null == null").unwrap();
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::Infix {
+            left: Box::new(Expression::Null.into()),
+            operator: Token::EQ,
+            right: Box::new(Expression::Null.into()),
+        })]
+    );
+}
+
+#[test]
+fn boolean_expression_test() {
+    let input = "true; false;
+    let flag = !true;
+    3 > 5 == false;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![
+            Statement::Expression(Expression::Boolean(true)),
+            Statement::Expression(Expression::Boolean(false)),
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("flag".into())).into(),
+                Expression::Prefix {
+                    operator: Token::Bang,
+                    right: Box::new(Expression::Boolean(true).into()),
+                }
+                .into(),
+            ),
+            Statement::Expression(Expression::Infix {
+                left: Box::new(
+                    Expression::Infix {
+                        left: Box::new(Expression::integer(3).into()),
+                        operator: Token::GT,
+                        right: Box::new(Expression::integer(5).into()),
+                    }
+                    .into()
+                ),
+                operator: Token::EQ,
+                right: Box::new(Expression::Boolean(false).into()),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn prefix_expression_test() {
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let tests = [
+        (
+            "!5;",
+            Expression::Prefix {
+                operator: Token::Bang,
+                right: Box::new(Expression::integer(5).into()),
+            },
+        ),
+        (
+            "-foobar;",
+            Expression::Prefix {
+                operator: Token::Minus,
+                right: Box::new(ident("foobar").into()),
+            },
+        ),
+        (
+            "!!true;",
+            Expression::Prefix {
+                operator: Token::Bang,
+                right: Box::new(
+                    Expression::Prefix {
+                        operator: Token::Bang,
+                        right: Box::new(Expression::Boolean(true).into()),
+                    }
+                    .into(),
+                ),
+            },
+        ),
+        (
+            "-f(x)[0];",
+            Expression::Prefix {
+                operator: Token::Minus,
+                right: Box::new(
+                    Expression::Index {
+                        left: Box::new(
+                            Expression::Call {
+                                function: Box::new(ident("f").into()),
+                                arguments: vec![ident("x").into()],
+                            }
+                            .into(),
+                        ),
+                        index: Box::new(Expression::integer(0).into()),
+                    }
+                    .into(),
+                ),
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, vec![Statement::Expression(expected)]);
+    }
+}
+
+#[test]
+fn infix_expression_test() {
+    let operators = [
+        ("+", Token::Plus),
+        ("-", Token::Minus),
+        ("*", Token::Asterisk),
+        ("/", Token::Slash),
+        ("<", Token::LT),
+        (">", Token::GT),
+        ("==", Token::EQ),
+        ("!=", Token::NotEq),
+    ];
+
+    for (source, operator) in operators {
+        let mut pars = Parser::from_source(&format!("5 {} x;", source));
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(
+            prog.statements,
+            vec![Statement::Expression(Expression::Infix {
+                left: Box::new(Expression::integer(5).into()),
+                operator,
+                right: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+            })]
+        );
+    }
+}
+
+#[test]
+fn operator_precedence_test() {
+    let tests = [
+        ("a + b * c", "(a + (b * c));"),
+        ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4));"),
+        ("-a * b", "((-a) * b);"),
+        ("3 + 4; -5 * 5", "(3 + 4);\n((-5) * 5);"),
+        ("!-a", "(!(-a));"),
+        ("a + b + c", "((a + b) + c);"),
+        ("a + b - c", "((a + b) - c);"),
+        ("a * b / c", "((a * b) / c);"),
+        ("a + b / c", "(a + (b / c));"),
+        ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f);"),
+        ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4));"),
+        (
+            "3 + 4 * 5 == 3 * 1 + 4 * 5",
+            "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)));",
+        ),
+        ("[1, 2 * 2, 3 + 3]", "[1, (2 * 2), (3 + 3)];"),
+        ("a * [1, 2][b * c] * d", "((a * [1, 2][(b * c)]) * d);"),
+        ("\"a\" + \"b\" == \"\"", "((\"a\" + \"b\") == \"\");"),
+        ("a * [1, 2][0]", "(a * [1, 2][0]);"),
+        ("myArray[1 + 1]", "myArray[(1 + 1)];"),
+        ("m[0][1] + f(x)[2]", "(m[0][1] + f(x)[2]);"),
+        ("-a[0]", "(-a[0]);"),
+        ("add(a + b, c * d)", "add((a + b), (c * d));"),
+        ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4);"),
+        ("(5 + 5) * 2", "((5 + 5) * 2);"),
+        ("2 / (5 + 5)", "(2 / (5 + 5));"),
+        ("-(5 + 5)", "(-(5 + 5));"),
+        ("!(true == true)", "(!(true == true));"),
+        ("((1 + 2) * 3)", "((1 + 2) * 3);"),
+        ("a = b == c", "(a = (b == c));"),
+        ("x = y = z + 1", "(x = (y = (z + 1)));"),
+        ("(a = 1) + 2", "((a = 1) + 2);"),
+        ("a = b = c", "(a = (b = c));"),
+        ("2 ** 3 ** 2", "(2 ** (3 ** 2));"),
+        ("(2 ** 3) ** 2", "((2 ** 3) ** 2);"),
+        ("2 * 3 ** 2", "(2 * (3 ** 2));"),
+        ("2 ** 3 * 2", "((2 ** 3) * 2);"),
+        ("-2 ** 2", "(-(2 ** 2));"),
+        ("2 ** -1", "(2 ** (-1));"),
+        ("a ** b[0]", "(a ** b[0]);"),
+        ("x = 2 ** y ** z", "(x = (2 ** (y ** z)));"),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.to_string(), expected);
+    }
+}
+
+#[test]
+fn grouped_expression_errors_test() {
+    let tests: [(&str, ErrorCheck); 2] = [
+        ("();", |errors| {
+            matches!(
+                errors,
+                [ParseError::NoPrefixParseFn {
+                    token: Token::Limiter(LimiterToken::RParen),
+                    ..
+                }]
+            )
+        }),
+        ("(1 + 2", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RParen),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn dangling_prefix_operator_test() {
+    let mut pars = Parser::from_source("let x = 1;\n-");
+    let prog = pars.parse_statements();
+
+    assert!(matches!(
+        pars.errors(),
+        [ParseError::UnexpectedEof { position }] if position.start == Position { line: 2, column: 2 }
+    ));
+    assert_eq!(prog.statements.len(), 1);
+}
+
+#[test]
+fn optional_semicolon_test() {
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let tests = [
+        ("foobar", vec![Statement::Expression(ident("foobar"))]),
+        (
+            "a; b",
+            vec![
+                Statement::Expression(ident("a")),
+                Statement::Expression(ident("b")),
+            ],
+        ),
+        (
+            "a;; b;",
+            vec![
+                Statement::Expression(ident("a")),
+                Statement::Expression(ident("b")),
+            ],
+        ),
+        (
+            "if (x) { x }",
+            vec![Statement::Expression(Expression::If {
+                condition: Box::new(ident("x").into()),
+                consequence: Box::new(
+                    Statement::Block(vec![Statement::Expression(ident("x")).into()]).into(),
+                ),
+                alternative: None,
+            })],
+        ),
+        (
+            "fn(x) { x; x }",
+            vec![Statement::Expression(Expression::FunctionLiteral {
+                parameters: vec![String::from("x")],
+                body: Box::new(
+                    Statement::Block(vec![
+                        Statement::Expression(ident("x")).into(),
+                        Statement::Expression(ident("x")).into(),
+                    ])
+                    .into(),
+                ),
+            })],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, expected, "{}", input);
+    }
+}
+
+#[test]
+fn if_expression_test() {
+    let input = "if (x < y) { x }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::If {
+            condition: Box::new(
+                Expression::Infix {
+                    left: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+                    operator: Token::LT,
+                    right: Box::new(Expression::Identifier(Token::Identifier("y".into())).into()),
+                }
+                .into()
+            ),
+            consequence: Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                    Token::Identifier("x".into())
+                ))
+                .into()])
+                .into()
+            ),
+            alternative: None,
+        })
+    );
+}
+
+#[test]
+fn if_else_expression_test() {
+    let input = "if (x) { x; } else { y; }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::If {
+            condition: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+            consequence: Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                    Token::Identifier("x".into())
+                ))
+                .into()])
+                .into()
+            ),
+            alternative: Some(Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                    Token::Identifier("y".into())
+                ))
+                .into()])
+                .into()
+            )),
+        })
+    );
+}
+
+#[test]
+fn nested_if_expression_test() {
+    let input = "if (a) { if (b) { c } else { d } } else { e }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let block =
+        |e: Expression| Box::new(Statement::Block(vec![Statement::Expression(e).into()]).into());
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::If {
+            condition: Box::new(ident("a").into()),
+            consequence: block(Expression::If {
+                condition: Box::new(ident("b").into()),
+                consequence: block(ident("c")),
+                alternative: Some(block(ident("d"))),
+            }),
+            alternative: Some(block(ident("e"))),
+        })]
+    );
+}
+
+#[test]
+fn else_if_chain_test() {
+    let ident =
+        |name: &str| Box::new(Expression::Identifier(Token::Identifier(name.into())).into());
+    let block = |n: i64| {
+        Box::new(
+            Statement::Block(vec![Statement::Expression(Expression::integer(n)).into()]).into(),
+        )
+    };
+    let tests = [
+        (
+            "if (a) { 1 } else if (b) { 2 } else { 3 }",
+            Expression::If {
+                condition: ident("a"),
+                consequence: block(1),
+                alternative: Some(Box::new(
+                    Statement::Expression(Expression::If {
+                        condition: ident("b"),
+                        consequence: block(2),
+                        alternative: Some(block(3)),
+                    })
+                    .into(),
+                )),
+            },
+        ),
+        (
+            "if (a) { 1 } else if (b) { 2 } else if (c) { 3 }",
+            Expression::If {
+                condition: ident("a"),
+                consequence: block(1),
+                alternative: Some(Box::new(
+                    Statement::Expression(Expression::If {
+                        condition: ident("b"),
+                        consequence: block(2),
+                        alternative: Some(Box::new(
+                            Statement::Expression(Expression::If {
+                                condition: ident("c"),
+                                consequence: block(3),
+                                alternative: None,
+                            })
+                            .into(),
+                        )),
+                    })
+                    .into(),
+                )),
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(
+            prog.statements,
+            vec![Statement::Expression(expected.clone())]
+        );
+        assert_eq!(prog.to_string(), format!("{};", expected));
+    }
+
+    let prog = crate::parse("if (a) { 1 } else if (b) { 2 } else { 3 }").unwrap();
+    assert_eq!(
+        prog.to_string(),
+        "if (a) { 1; } else if (b) { 2; } else { 3; };"
+    );
+}
+
+#[test]
+fn if_expression_errors_test() {
+    let tests: [(&str, ErrorCheck); 4] = [
+        ("if (x) { y } else if x { z }", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("if (x) y", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::LBrace),
+                    found: Some(Token::Identifier(y)),
+                    ..
+                }] if &**y == "y"
+            )
+        }),
+        ("if (x) { y } else z", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::LBrace),
+                    found: Some(Token::Identifier(z)),
+                    ..
+                }] if &**z == "z"
+            )
+        }),
+        ("if (x) {\n y", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBrace),
+                    found: None,
+                    position,
+                }] if position.start.line == 2
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn while_statement_test() {
+    let input = "while (x < 10) { let x = x + 1; }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::While {
+            condition: Expression::Infix {
+                left: Box::new(Expression::Identifier(Token::Identifier("x".into())).into()),
+                operator: Token::LT,
+                right: Box::new(Expression::integer(10).into()),
+            }
+            .into(),
+            body: Box::new(
+                Statement::Block(vec![Statement::Let(
+                    Expression::Identifier(Token::Identifier("x".into())).into(),
+                    Expression::Infix {
+                        left: Box::new(
+                            Expression::Identifier(Token::Identifier("x".into())).into()
+                        ),
+                        operator: Token::Plus,
+                        right: Box::new(Expression::integer(1).into()),
+                    }
+                    .into(),
+                )
+                .into()])
+                .into()
+            ),
+        }]
+    );
+}
+
+#[test]
+fn nested_while_statement_test() {
+    let input = "while (a) { while (b) { c } d } while (e) {}";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.to_string(),
+        "while (a) { while (b) { c; } d; }\nwhile (e) {}"
+    );
+}
+
+#[test]
+fn while_statement_errors_test() {
+    let tests: [(&str, ErrorCheck); 5] = [
+        ("while () {}", |errors| {
+            matches!(
+                errors,
+                [ParseError::NoPrefixParseFn {
+                    token: Token::Limiter(LimiterToken::RParen),
+                    ..
+                }]
+            )
+        }),
+        ("while x {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        found: Some(Token::Identifier(_)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("while (x {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::RParen),
+                        found: Some(Token::Limiter(LimiterToken::LBrace)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("while (x)", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::LBrace),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("while (x) { while (y) { z }", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBrace),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn for_statement_test() {
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let input = "for (let i = 0; i < 10; i + 1) { puts(i); }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::For {
+            init: Some(Box::new(
+                Statement::Let(ident("i").into(), Expression::integer(0).into()).into()
+            )),
+            condition: Some(
+                Expression::Infix {
+                    left: Box::new(ident("i").into()),
+                    operator: Token::LT,
+                    right: Box::new(Expression::integer(10).into()),
+                }
+                .into()
+            ),
+            post: Some(Box::new(
+                Statement::Expression(Expression::Infix {
+                    left: Box::new(ident("i").into()),
+                    operator: Token::Plus,
+                    right: Box::new(Expression::integer(1).into()),
+                })
+                .into()
+            )),
+            body: Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Call {
+                    function: Box::new(ident("puts").into()),
+                    arguments: vec![ident("i").into()],
+                })
+                .into()])
+                .into()
+            ),
+        }]
+    );
+}
+
+#[test]
+fn for_statement_clauses_test() {
+    let tests = [
+        ("for (;;) {}", "for (;;) {}"),
+        ("for (; x;) { y }", "for (; x;) { y; }"),
+        ("for (let i = 0;;) {}", "for (let i = 0;;) {}"),
+        ("for (;; f(x)) {}", "for (;; f(x)) {}"),
+        ("for (;; i = i + 1) {}", "for (;; (i = (i + 1))) {}"),
+        (
+            "for (let i = 0; i < n; i + 1) { for (;;) {} }",
+            "for (let i = 0; (i < n); (i + 1)) { for (;;) {} }",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.to_string(), expected);
+    }
+}
+
+#[test]
+fn for_statement_errors_test() {
+    let tests: [(&str, ErrorCheck); 6] = [
+        (
+            "for (x) {}",
+            |errors| matches!(errors, [ParseError::ForClauses { position }] if position.start.column == 7),
+        ),
+        (
+            "for (x; y) {}",
+            |errors| matches!(errors, [ParseError::ForClauses { position }] if position.start.column == 10),
+        ),
+        (
+            "for (;;;) {}",
+            |errors| matches!(errors, [ParseError::ForClauses { position }] if position.start.column == 8),
+        ),
+        ("for (a; b; c; f(d, e)) { g; h } i", |errors| {
+            matches!(errors, [ParseError::ForClauses { .. }])
+        }),
+        ("for x {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("for (;;)", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::LBrace),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+
+    let errors = crate::parse("for (x) {}").unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "expected 3 clauses separated by `;` in for loop header at line 1, column 7"
+    );
+}
+
+#[test]
+fn assign_expression_test() {
+    let ident =
+        |name: &str| Box::new(Expression::Identifier(Token::Identifier(name.into())).into());
+    let tests = [
+        (
+            "x = x + 1;",
+            Expression::Assign {
+                target: ident("x"),
+                value: Box::new(
+                    Expression::Infix {
+                        left: ident("x"),
+                        operator: Token::Plus,
+                        right: Box::new(Expression::integer(1).into()),
+                    }
+                    .into(),
+                ),
+            },
+        ),
+        (
+            "a = b = 3",
+            Expression::Assign {
+                target: ident("a"),
+                value: Box::new(
+                    Expression::Assign {
+                        target: ident("b"),
+                        value: Box::new(Expression::integer(3).into()),
+                    }
+                    .into(),
+                ),
+            },
+        ),
+        (
+            "arr[0] = 5",
+            Expression::Assign {
+                target: Box::new(
+                    Expression::Index {
+                        left: ident("arr"),
+                        index: Box::new(Expression::integer(0).into()),
+                    }
+                    .into(),
+                ),
+                value: Box::new(Expression::integer(5).into()),
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, vec![Statement::Expression(expected)]);
+    }
+}
+
+#[test]
+fn invalid_assignment_target_test() {
+    let tests: [(&str, ErrorCheck); 4] = [
+        ("5 = x", |errors| {
+            matches!(
+                errors,
+                [ParseError::InvalidAssignmentTarget { target, position }]
+                    if target == "5" && position.start.column == 3
+            )
+        }),
+        ("a + b = c", |errors| {
+            matches!(
+                errors,
+                [ParseError::InvalidAssignmentTarget { target, .. }] if target == "(a + b)"
+            )
+        }),
+        ("f() = 1; let x = 2;", |errors| {
+            matches!(errors, [ParseError::InvalidAssignmentTarget { .. }])
+        }),
+        ("a = ", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+
+    let errors = crate::parse("5 = x").unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "can't assign to `5` at line 1, column 3"
+    );
+}
+
+#[test]
+fn function_literal_test() {
+    let input = "fn(x, y) { x; }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::FunctionLiteral {
+            parameters: vec![String::from("x"), String::from("y")],
+            body: Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                    Token::Identifier("x".into())
+                ))
+                .into()])
+                .into()
+            ),
+        })
+    );
+}
+
+#[test]
+fn function_literal_in_let_statement_test() {
+    let input = "let add = fn(a, b) { a + b; };";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Let(
+            ident("add").into(),
+            Expression::FunctionLiteral {
+                parameters: vec![String::from("a"), String::from("b")],
+                body: Box::new(
+                    Statement::Block(vec![Statement::Expression(Expression::Infix {
+                        left: Box::new(ident("a").into()),
+                        operator: Token::Plus,
+                        right: Box::new(ident("b").into()),
+                    })
+                    .into()])
+                    .into()
+                ),
+            }
+            .into(),
+        )]
+    );
+}
+
+#[test]
+fn function_parameters_errors_test() {
+    let tests: [(&str, ErrorCheck); 3] = [
+        ("fn(x,) {}", |errors| {
+            matches!(
+                errors,
+                [ParseError::ExpectedIdentifier {
+                    found: Some(Token::Limiter(LimiterToken::RParen)),
+                    ..
+                }]
+            )
+        }),
+        ("fn(x y) {}", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RParen),
+                    found: Some(Token::Identifier(_)),
+                    ..
+                }]
+            )
+        }),
+        ("fn(1) {}", |errors| {
+            matches!(
+                errors,
+                [ParseError::ExpectedIdentifier {
+                    found: Some(Token::Literal(_)),
+                    ..
+                }]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn function_literal_without_parameters_test() {
+    let input = "fn() {}";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::FunctionLiteral {
+            parameters: vec![],
+            body: Box::new(Statement::Block(vec![]).into()),
+        })
+    );
+}
+
+#[test]
+fn function_statement_test() {
+    let tests = [
+        ("fn add(a, b) { a + b }", "let add = fn(a, b) { a + b; };"),
+        ("fn add(a, b) { a + b };", "let add = fn(a, b) { a + b; };"),
+        (
+            "fn fib(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } } fib(10)",
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(10)",
+        ),
+        ("fn nothing() {}", "let nothing = fn() {};"),
+    ];
+
+    for (sugar, desugared) in tests {
+        let prog = crate::parse(sugar).unwrap();
+        assert_eq!(prog, crate::parse(desugared).unwrap(), "{}", sugar);
+    }
+
+    // without a name it's still a function literal
+    let prog = crate::parse("fn(x) { x }(5)").unwrap();
+    assert_eq!(prog.to_string(), "fn(x) { x; }(5);");
+
+    let prog = crate::parse("fn add(a, b) { a + b }").unwrap();
+    let Statement::Let(_, function) = &*prog.statements[0] else {
+        panic!("{:?}", prog.statements[0]);
+    };
+    assert_eq!(
+        function.span,
+        Span {
+            start: Position { line: 1, column: 1 },
+            end: Position {
+                line: 1,
+                column: 23
+            }
+        }
+    );
+}
+
+#[test]
+fn function_statement_errors_test() {
+    let tests: [(&str, ErrorCheck); 3] = [
+        ("fn 5() {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        found: Some(Token::Literal(_)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("fn add {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        found: Some(Token::Limiter(LimiterToken::LBrace)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("fn add(a, b)", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::LBrace),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let errors = crate::parse(input).unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn macro_literal_test() {
+    let input = "macro(x, y) { x + y; }";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::MacroLiteral {
+            parameters: vec![String::from("x"), String::from("y")],
+            body: Box::new(
+                Statement::Block(vec![Statement::Expression(Expression::Infix {
+                    left: Box::new(ident("x").into()),
+                    operator: Token::Plus,
+                    right: Box::new(ident("y").into()),
+                })
+                .into()])
+                .into()
+            ),
+        })
+    );
+    assert_eq!(prog.to_string(), "macro(x, y) { (x + y); };");
+}
+
+#[test]
+fn macro_literal_in_let_statement_test() {
+    let input = "let unless = macro() {};";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Let(
+            Expression::Identifier(Token::Identifier("unless".into())).into(),
+            Expression::MacroLiteral {
+                parameters: vec![],
+                body: Box::new(Statement::Block(vec![]).into()),
+            }
+            .into(),
+        )]
+    );
+}
+
+#[test]
+fn macro_parameters_errors_test() {
+    let tests: [(&str, ErrorCheck); 2] = [
+        ("macro(x,) {}", |errors| {
+            matches!(
+                errors,
+                [ParseError::ExpectedIdentifier {
+                    found: Some(Token::Limiter(LimiterToken::RParen)),
+                    ..
+                }]
+            )
+        }),
+        ("macro x {}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::LParen),
+                        found: Some(Token::Identifier(_)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn quote_unquote_test() {
+    let input = "quote(1 + unquote(x))";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::Call {
+            function: Box::new(ident("quote").into()),
+            arguments: vec![Expression::Infix {
+                left: Box::new(Expression::integer(1).into()),
+                operator: Token::Plus,
+                right: Box::new(
+                    Expression::Call {
+                        function: Box::new(ident("unquote").into()),
+                        arguments: vec![ident("x").into()],
+                    }
+                    .into()
+                ),
+            }
+            .into()],
+        })]
+    );
+    assert_eq!(prog.to_string(), "quote((1 + unquote(x)));");
+}
+
+#[test]
+fn call_expression_test() {
+    let input = "add(a, fn(x) { x; });";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::Call {
+            function: Box::new(Expression::Identifier(Token::Identifier("add".into())).into()),
+            arguments: vec![
+                Expression::Identifier(Token::Identifier("a".into())).into(),
+                Expression::FunctionLiteral {
+                    parameters: vec![String::from("x")],
+                    body: Box::new(
+                        Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                            Token::Identifier("x".into())
+                        ))
+                        .into()])
+                        .into()
+                    ),
+                }
+                .into(),
+            ],
+        })
+    );
+}
+
+#[test]
+fn call_arguments_test() {
+    let tests = [
+        ("add(1, 2 * 3, 4 + 5);", "add(1, (2 * 3), (4 + 5));"),
+        ("fn(x) { x; }(5)", "fn(x) { x; }(5);"),
+        ("add(add(1, 2), 3)", "add(add(1, 2), 3);"),
+        ("a + add(b * c) + d", "((a + add((b * c))) + d);"),
+        (
+            "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+            "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)));",
+        ),
+        (
+            "add(a + b + c * d / f + g)",
+            "add((((a + b) + ((c * d) / f)) + g));",
+        ),
+        ("-f(x)", "(-f(x));"),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.to_string(), expected);
+    }
+}
+
+#[test]
+fn unclosed_call_arguments_test() {
+    let tests: [(&str, ErrorCheck); 3] = [
+        ("add(1, 2", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RParen),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("add(1,", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+        ("add(", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn call_expression_without_arguments_test() {
+    let input = "foo(); bar()(baz);";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 2);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::Call {
+            function: Box::new(Expression::Identifier(Token::Identifier("foo".into())).into()),
+            arguments: vec![],
+        })
+    );
+    assert_eq!(
+        prog.statements[1],
+        Statement::Expression(Expression::Call {
+            function: Box::new(
+                Expression::Call {
+                    function: Box::new(
+                        Expression::Identifier(Token::Identifier("bar".into())).into()
+                    ),
+                    arguments: vec![],
+                }
+                .into()
+            ),
+            arguments: vec![Expression::Identifier(Token::Identifier("baz".into())).into()],
+        })
+    );
+}
+
+#[test]
+fn string_literal_expression_test() {
+    let input = "\"hello\";";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::StringLiteral(Token::StringLiteral(
+            "hello".to_string()
+        )))
+    );
+}
+
+#[test]
+fn string_literal_in_expressions_test() {
+    let string =
+        |s: &str| Box::new(Expression::StringLiteral(Token::StringLiteral(String::from(s))).into());
+    let tests = [
+        (
+            "\"\"",
+            Expression::StringLiteral(Token::StringLiteral(String::new())),
+        ),
+        (
+            "\"hello\" + \" \" + \"world\"",
+            Expression::Infix {
+                left: Box::new(
+                    Expression::Infix {
+                        left: string("hello"),
+                        operator: Token::Plus,
+                        right: string(" "),
+                    }
+                    .into(),
+                ),
+                operator: Token::Plus,
+                right: string("world"),
+            },
+        ),
+        (
+            "len(\"four\", \"\")",
+            Expression::Call {
+                function: Box::new(Expression::Identifier(Token::Identifier("len".into())).into()),
+                arguments: vec![*string("four"), *string("")],
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, vec![Statement::Expression(expected)]);
+    }
+}
+
+#[test]
+fn array_literal_test() {
+    let input = "[a, b(c), fn(x) { x }]";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+    assert_eq!(prog.statements.len(), 1);
+
+    assert_eq!(
+        prog.statements[0],
+        Statement::Expression(Expression::ArrayLiteral(vec![
+            Expression::Identifier(Token::Identifier("a".into())).into(),
+            Expression::Call {
+                function: Box::new(Expression::Identifier(Token::Identifier("b".into())).into()),
+                arguments: vec![Expression::Identifier(Token::Identifier("c".into())).into()],
+            }
+            .into(),
+            Expression::FunctionLiteral {
+                parameters: vec![String::from("x")],
+                body: Box::new(
+                    Statement::Block(vec![Statement::Expression(Expression::Identifier(
+                        Token::Identifier("x".into())
+                    ))
+                    .into()])
+                    .into()
+                ),
+            }
+            .into(),
+        ]))
+    );
+}
+
+#[test]
+fn array_literal_shapes_test() {
+    let int = Expression::integer;
+    let tests = [
+        ("[]", Expression::ArrayLiteral(vec![])),
+        ("[1]", Expression::ArrayLiteral(vec![int(1).into()])),
+        (
+            "[[1], [2]]",
+            Expression::ArrayLiteral(vec![
+                Expression::ArrayLiteral(vec![int(1).into()]).into(),
+                Expression::ArrayLiteral(vec![int(2).into()]).into(),
+            ]),
+        ),
+        (
+            "[1, 2 * 2, 3 + 3]",
+            Expression::ArrayLiteral(vec![
+                int(1).into(),
+                Expression::Infix {
+                    left: Box::new(int(2).into()),
+                    operator: Token::Asterisk,
+                    right: Box::new(int(2).into()),
+                }
+                .into(),
+                Expression::Infix {
+                    left: Box::new(int(3).into()),
+                    operator: Token::Plus,
+                    right: Box::new(int(3).into()),
+                }
+                .into(),
+            ]),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, vec![Statement::Expression(expected)]);
+    }
+}
+
+#[test]
+fn unclosed_array_literal_test() {
+    let tests: [(&str, ErrorCheck); 3] = [
+        ("[1, 2", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBracket),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("[1,", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+        ("[", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn index_expression_test() {
+    let ident = |name: &str| Expression::Identifier(Token::Identifier(name.into()));
+    let tests = [
+        (
+            "a[i]",
+            Expression::Index {
+                left: Box::new(ident("a").into()),
+                index: Box::new(ident("i").into()),
+            },
+        ),
+        (
+            "a[f(i)]",
+            Expression::Index {
+                left: Box::new(ident("a").into()),
+                index: Box::new(
+                    Expression::Call {
+                        function: Box::new(ident("f").into()),
+                        arguments: vec![ident("i").into()],
+                    }
+                    .into(),
+                ),
+            },
+        ),
+        (
+            "[x, y][i]",
+            Expression::Index {
+                left: Box::new(
+                    Expression::ArrayLiteral(vec![ident("x").into(), ident("y").into()]).into(),
+                ),
+                index: Box::new(ident("i").into()),
+            },
+        ),
+        (
+            "a[i][j]",
+            Expression::Index {
+                left: Box::new(
+                    Expression::Index {
+                        left: Box::new(ident("a").into()),
+                        index: Box::new(ident("i").into()),
+                    }
+                    .into(),
+                ),
+                index: Box::new(ident("j").into()),
+            },
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.statements, vec![Statement::Expression(expected)]);
+    }
+}
+
+#[test]
+fn unclosed_index_expression_test() {
+    let tests: [(&str, ErrorCheck); 3] = [
+        ("a[1", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBracket),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("a[1; b", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBracket),
+                    found: Some(Token::Limiter(LimiterToken::Semicolon)),
+                    ..
+                }]
+            )
+        }),
+        ("a[", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn hash_literal_test() {
+    let input = "{\"one\": a, \"two\": f(b)}";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::HashLiteral(vec![
+            (
+                Expression::StringLiteral(Token::StringLiteral(String::from("one"))).into(),
+                Expression::Identifier(Token::Identifier("a".into())).into(),
+            ),
+            (
+                Expression::StringLiteral(Token::StringLiteral(String::from("two"))).into(),
+                Expression::Call {
+                    function: Box::new(
+                        Expression::Identifier(Token::Identifier("f".into())).into()
+                    ),
+                    arguments: vec![Expression::Identifier(Token::Identifier("b".into())).into()],
+                }
+                .into(),
+            ),
+        ]))]
+    );
+}
+
+#[test]
+fn empty_hash_literal_test() {
+    let mut pars = Parser::from_source("{}");
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Expression(Expression::HashLiteral(vec![]))]
+    );
+}
+
+#[test]
+fn hash_literal_with_expressions_test() {
+    let tests = [
+        ("{1 + 1: 2 * 2}", "{(1 + 1): (2 * 2)};"),
+        ("{\"one\": 1, \"two\": 2}", "{\"one\": 1, \"two\": 2};"),
+        ("{true: [1], f(x): {}}", "{true: [1], f(x): {}};"),
+        ("let h = {};", "let h = {};"),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let prog = pars.parse_program().unwrap();
+        assert_eq!(prog.to_string(), expected);
+    }
+}
+
+#[test]
+fn hash_literal_errors_test() {
+    let tests: [(&str, ErrorCheck); 4] = [
+        ("{\"one\" 1}", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::Colon),
+                    found: Some(Token::Literal(_)),
+                    ..
+                }]
+            )
+        }),
+        ("{\"one\": 1", |errors| {
+            matches!(
+                errors,
+                [ParseError::UnexpectedToken {
+                    expected: Token::Limiter(LimiterToken::RBrace),
+                    found: None,
+                    ..
+                }]
+            )
+        }),
+        ("{\"one\": 1 \"two\": 2}", |errors| {
+            matches!(
+                errors,
+                [
+                    ParseError::UnexpectedToken {
+                        expected: Token::Limiter(LimiterToken::RBrace),
+                        found: Some(Token::StringLiteral(_)),
+                        ..
+                    },
+                    ..
+                ]
+            )
+        }),
+        ("{", |errors| {
+            matches!(errors, [ParseError::UnexpectedEof { .. }])
+        }),
+    ];
+
+    for (input, expected) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+        assert!(expected(&errors), "{}: {:?}", input, errors);
+    }
+}
+
+#[test]
+fn expect_peek_error_test() {
+    let input = "let x 5;
+    let = 10;
+    add(x;
+    let z = w;
+    ";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_statements();
+
+    assert!(matches!(
+        pars.errors(),
+        [
+            ParseError::UnexpectedToken {
+                expected: Token::Assign,
+                found: Some(Token::Literal(five)),
+                position: p1,
+            },
+            ParseError::ExpectedIdentifier {
+                found: Some(Token::Assign),
+                position: p2,
+            },
+            ParseError::UnexpectedToken {
+                expected: Token::Limiter(LimiterToken::RParen),
+                found: Some(Token::Limiter(LimiterToken::Semicolon)),
+                position: p3,
+            },
+        ] if five == "5" && p1.start.line == 1 && p2.start.line == 2 && p3.start.line == 3
+    ));
+    assert!(prog.statements.contains(
+        &Statement::Let(
+            Expression::Identifier(Token::Identifier("z".into())).into(),
+            Expression::Identifier(Token::Identifier("w".into())).into(),
+        )
+        .into()
+    ));
+}
+
+#[test]
+fn error_messages_test() {
+    let input = "let x 5;
+    let = 10;
+    let y = ;
+    let 838383;
+    let z = 99999999999999999999;
+    let w = (1";
+
+    let mut pars = Parser::from_source(input);
+    let errors = pars.parse_program().unwrap_err();
+
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "expected next token to be `=`, got `5` instead at line 1, column 7",
+            "expected next token to be an identifier, got `=` instead at line 2, column 9",
+            "unexpected token `;` at start of expression at line 3, column 13",
+            "expected next token to be an identifier, got `838383` instead at line 4, column 9",
+            "integer literal 99999999999999999999 is too big at line 5, column 13",
+            "expected next token to be `)`, got end of input instead at line 6, column 15",
+        ]
+    );
+}
+
+#[test]
+fn lex_errors_are_reported_test() {
+    let mut pars = Parser::from_source("let x = @;");
+    let errors = pars.parse_program().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        ParseError::Lex(e) if e.found == '@' && e.position == Position { line: 1, column: 9 }
+    ));
+}
+
+#[test]
+fn non_ascii_digits_test() {
+    // numeric but not digits a literal can be made of
+    let tests = [("let x = ²;", '²', 9), ("٣ + 1", '٣', 1)];
+
+    for (input, found, column) in tests {
+        let mut pars = Parser::from_source(input);
+        let errors = pars.parse_program().unwrap_err();
+
+        assert_eq!(errors.len(), 1, "{}", input);
+        assert!(
+            matches!(
+                &errors[0],
+                ParseError::Lex(e) if e.found == found && e.position == Position { line: 1, column }
+            ),
+            "{}: {:?}",
+            input,
+            errors
+        );
+    }
+}
+
+#[test]
+fn illegal_token_reported_once_test() {
+    let mut pars = Parser::from_source("let é = 1; é");
+    let errors = pars.parse_program().unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e, ParseError::Lex(e) if e.found == 'é')));
+}
+
+#[test]
+fn multiple_errors_test() {
+    let input = "let x 5 = y; let = (z) let w = v;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_statements();
+
+    assert_eq!(pars.errors().len(), 2);
+    assert_eq!(
+        prog.statements,
+        vec![Statement::Let(
+            Expression::Identifier(Token::Identifier("w".into())).into(),
+            Expression::Identifier(Token::Identifier("v".into())).into(),
+        )]
+    );
+}
+
+#[test]
+fn no_prefix_parse_fn_test() {
+    let input = "!;
+let = 5;
+let x = 1;
++ 5;
+} x;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_statements();
+
+    let messages: Vec<String> = pars.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "unexpected token `;` at start of expression at line 1, column 2",
+            "expected next token to be an identifier, got `=` instead at line 2, column 5",
+            "unexpected token `+` at start of expression at line 4, column 1",
+            "unexpected token `}` at start of expression at line 5, column 1",
+        ]
+    );
+    assert!(matches!(
+        pars.errors()[0],
+        ParseError::NoPrefixParseFn {
+            token: Token::Limiter(LimiterToken::Semicolon),
+            ..
+        }
+    ));
+    assert_eq!(prog.to_string(), "let x = 1;\nx;");
+
+    let mut pars = Parser::from_source("+ 5; x;");
+    let prog = pars.parse_statements();
+    assert_eq!(pars.errors().len(), 1);
+    assert_eq!(prog.to_string(), "x;");
+}
+
+#[test]
+fn error_recovery_test() {
+    let input = "let a = 1;
+let b = 2;
+let c = * 3;
+let d = fn(x) {
+    let y = x +;
+    y
+};
+if (a) { b } else { c }
+let e = 4
+let f = (5;
+let g = 6;";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_statements();
+
+    let lines: Vec<usize> = pars
+        .errors()
+        .iter()
+        .map(|e| match e {
+            ParseError::NoPrefixParseFn { position, .. }
+            | ParseError::UnexpectedToken { position, .. } => position.start.line,
+            e => panic!("unexpected error {:?}", e),
+        })
+        .collect();
+    assert_eq!(lines, [3, 5, 10]);
+    assert_eq!(
+        prog.to_string(),
+        "let a = 1;
+let b = 2;
+let d = fn(x) { y; };
+if (a) { b; } else { c; };
+let e = 4;
+let g = 6;"
+    );
+}
+
+#[test]
+fn parse_program_result_test() {
+    let mut pars = Parser::from_source("let a = 1; let b 2; let = 3;");
+    let errors = pars.parse_program().unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [
+            ParseError::UnexpectedToken {
+                expected: Token::Assign,
+                ..
+            },
+            ParseError::ExpectedIdentifier { .. },
+        ]
+    ));
+
+    let mut pars = Parser::from_source("let a = 1; a;");
+    assert_eq!(pars.parse_program().map(|p| p.statements.len()), Ok(2));
+}
+
+#[test]
+fn display_test() {
+    let input = "let x = y;
+    return   add( x,y );
+    if (a) {b} else { c; return d }
+    fn (a, b) { return }; f()[i][j]; !-x
+    let s = [\"a\\n\", {\"k\": v}, {}];";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    assert_eq!(
+        prog.to_string(),
+        "let x = y;
+return add(x, y);
+if (a) { b; } else { c; return d; };
+fn(a, b) { return; };
+f()[i][j];
+(!(-x));
+let s = [\"a\\n\", {\"k\": v}, {}];"
+    );
+}
+
+#[test]
+fn display_round_trip_test() {
+    let input = "let add = fn(x, y) { return x; };
+    let r = if (add(x)[i]) { {\"a\": [y, \"b\"]} } else { fn() { z } };
+    r(p)(q);
+    let n = -(1 + 2) * !(a == b) / c[0 - 1];
+    (-2) ** 2;
+    (-a)[0];
+    (-f)(1);";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let mut pars = Parser::from_source(&prog.to_string());
+    let reparsed = pars.parse_program().unwrap();
+    assert_eq!(reparsed, prog);
+}
+
+#[test]
+fn spans_test() {
+    let input = "let x = 1;
+    (x + 2) * f(x);";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let span = |(line, column), (end_line, end_column)| Span {
+        start: Position { line, column },
+        end: Position {
+            line: end_line,
+            column: end_column,
+        },
+    };
+    assert_eq!(prog.statements[0].span, span((1, 1), (1, 11)));
+    assert_eq!(prog.statements[1].span, span((2, 5), (2, 20)));
+
+    let Statement::Let(ident, value) = &prog.statements[0].node else {
+        panic!("not a let statement: {}", prog.statements[0]);
+    };
+    assert_eq!(ident.span, span((1, 5), (1, 6)));
+    assert_eq!(value.span, span((1, 9), (1, 10)));
+
+    let Statement::Expression(Expression::Infix { left, right, .. }) = &prog.statements[1].node
+    else {
+        panic!("not an infix expression: {}", prog.statements[1]);
+    };
+    assert_eq!(left.span, span((2, 5), (2, 12)));
+    assert_eq!(right.span, span((2, 15), (2, 19)));
+    let Expression::Call { arguments, .. } = &right.node else {
+        panic!("not a call: {}", right);
+    };
+    assert_eq!(arguments[0].span, span((2, 17), (2, 18)));
+}
+
+#[test]
+fn block_spans_test() {
+    let input = "if (a) {
+  b
+} else {
+  c
+}";
+
+    let mut pars = Parser::from_source(input);
+    let prog = pars.parse_program().unwrap();
+
+    let Statement::Expression(Expression::If {
+        consequence,
+        alternative: Some(alternative),
+        ..
+    }) = &prog.statements[0].node
+    else {
+        panic!("not an if expression: {}", prog.statements[0]);
+    };
+    assert_eq!(consequence.span.start, Position { line: 1, column: 8 });
+    assert_eq!(consequence.span.end, Position { line: 3, column: 2 });
+    assert_eq!(alternative.span.start, Position { line: 3, column: 8 });
+    assert_eq!(alternative.span.end, Position { line: 5, column: 2 });
+    assert_eq!(prog.statements[0].span.end, Position { line: 5, column: 2 });
+}
+
+#[test]
+fn parser_iterator_test() {
+    let input = "let a = 1; let b = 2; let c = 3; let d = 4; let e = 5;";
+
+    let mut pars = Parser::from_source(input);
+    let first_two: Vec<Statement> = pars
+        .by_ref()
+        .take(2)
+        .map(|result| result.unwrap().node)
+        .collect();
+
+    assert_eq!(
+        first_two,
+        vec![
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("a".into())).into(),
+                Expression::integer(1).into(),
+            ),
+            Statement::Let(
+                Expression::Identifier(Token::Identifier("b".into())).into(),
+                Expression::integer(2).into(),
+            ),
+        ]
+    );
+    // the parser is on the second statement's `;` and the lexer only read as far as the `let`
+    // after it
+    assert_eq!(
+        pars.curr_token,
+        Some(Token::Limiter(LimiterToken::Semicolon))
+    );
+    assert_eq!(pars.peek_token, Some(Token::Let));
+    assert_eq!(pars.lexer.token_span(), pars.peek_span);
+    assert_eq!(
+        pars.peek_span.start,
+        Position {
+            line: 1,
+            column: 23
+        }
+    );
+
+    assert_eq!(pars.by_ref().count(), 3);
+    assert!(pars.next().is_none());
+}
+
+#[test]
+fn parser_iterator_errors_test() {
+    let input = "let x 5; x; let = 10; @";
+
+    let results: Vec<Result<Spanned<Statement>, ParseError>> = Parser::from_source(input).collect();
+
+    assert!(
+        matches!(
+            results.as_slice(),
+            [
+                Err(ParseError::UnexpectedToken { .. }),
+                Ok(statement),
+                Err(ParseError::ExpectedIdentifier { .. }),
+                Err(ParseError::Lex(_)),
+            ] if statement.to_string() == "x;"
+        ),
+        "{:?}",
+        results
+    );
+
+    assert!(Parser::from_source("").next().is_none());
+}
+
+#[test]
+fn nesting_too_deep_test() {
+    let deep = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+    let errors = crate::parse(&deep).unwrap_err();
+    assert!(
+        matches!(
+            errors[..],
+            [ParseError::NestingTooDeep {
+                limit: DEFAULT_MAX_DEPTH,
+                ..
+            }]
+        ),
+        "{:?}",
+        errors
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "nesting deeper than 256 levels at line 1, column 257"
+    );
+
+    let deep_blocks = "while (x) { ".repeat(1_000);
+    let errors = crate::parse(&deep_blocks).unwrap_err();
+    assert!(
+        matches!(errors[..], [ParseError::NestingTooDeep { .. }]),
+        "{:?}",
+        errors
+    );
+
+    let tests = [
+        format!("{}1{}", "(".repeat(50), ")".repeat(50)),
+        format!("{}1{}", "[".repeat(50), "]".repeat(50)),
+        format!("{}1{}", "f(".repeat(50), ")".repeat(50)),
+        format!("{}1", "-".repeat(50)),
+        format!("{}{}", "fn() { ".repeat(50), "}".repeat(50)),
+    ];
+    for input in tests {
+        assert!(crate::parse(&input).is_ok(), "{}", input);
+    }
+
+    let mut pars = Parser::from_source("[[1]]").with_max_depth(3);
+    assert!(pars.parse_program().is_ok());
+}
+
+#[test]
+fn trailing_commas_test() {
+    let tests = [
+        ("add(1, 2,)", "add(1, 2);"),
+        ("add(\n  1,\n  2,\n)", "add(1, 2);"),
+        ("f(x,)", "f(x);"),
+        ("[1, 2, 3,]", "[1, 2, 3];"),
+        ("[\n  [1,],\n]", "[[1]];"),
+        ("{\"a\": 1,}", "{\"a\": 1};"),
+        (
+            "{\n  \"a\": 1,\n  \"b\": [2,],\n}",
+            "{\"a\": 1, \"b\": [2]};",
+        ),
+    ];
+    for (input, expected) in tests {
+        let prog = crate::parse(input).unwrap();
+        assert_eq!(prog.to_string(), expected, "{}", input);
+    }
+
+    let no_prefix = |errors: &[ParseError]| {
+        matches!(
+            errors,
+            [
+                ParseError::NoPrefixParseFn {
+                    token: Token::Limiter(LimiterToken::Comma),
+                    ..
+                },
+                ..
+            ]
+        )
+    };
+    for input in [
+        "add(,)",
+        "add(1,,2)",
+        "add(1, 2,,)",
+        "[,]",
+        "[1,,2]",
+        "{,}",
+        "{\"a\": 1,,}",
+    ] {
+        let errors = crate::parse(input).unwrap_err();
+        assert!(no_prefix(&errors), "{}: {:?}", input, errors);
+    }
+}
@@ -1,29 +1,364 @@
-use std::io::{self, stdin, stdout, Write};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, stderr, stdin, stdout, Read, Write};
+use std::mem::take;
+use std::process::ExitCode;
+use std::rc::Rc;
 
-use crate::lexer::Lexer;
+use monkey_rs::environment::Environment;
+use monkey_rs::evaluator::eval_program;
+use monkey_rs::lexer::{tokenize, Lexer, LimiterToken, Token};
+use monkey_rs::object::Object;
+
+use crate::color;
 
 const PROMPT: &str = ">> ";
+/// Shown while the lines so far leave a bracket open.
+const CONTINUATION_PROMPT: &str = ".. ";
+
+/// How far each input is taken before its result is printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplMode {
+    /// Prints the tokens the lexer reads, one per line.
+    Tokens,
+    /// Prints the statements the parser builds, one per line.
+    Parse,
+    /// Prints what the input evaluates to.
+    Eval,
+}
 
-pub fn start() -> Result<(), io::Error> {
+pub fn start(mode: ReplMode) -> Result<(), io::Error> {
     // If it fails to read or write it'll just propagate the error
     let in_handle = stdin();
     let mut out_handle = stdout();
+    // shared by every line, so bindings carry over from one to the next
+    let mut env = Rc::new(RefCell::new(Environment::new()));
+    let colored = color::enabled();
     println!("REPL starting...");
 
+    let mut input = String::new();
     loop {
-        print!("{}", PROMPT);
-        out_handle.flush()?;
-        let mut input = String::new();
-        let scanned = in_handle.read_line(&mut input);
-        if scanned.is_ok() {
-            let lex = Lexer::from(String::from(input.trim()));
-            lex.into_iter()
-                .map(|token| {
-                    println!("{:?}", token);
-                })
-                .for_each(drop);
+        let prompt = if input.is_empty() {
+            PROMPT
         } else {
-            return Ok(());
+            CONTINUATION_PROMPT
         };
+        if colored {
+            print!("{}", color::green(prompt));
+        } else {
+            print!("{}", prompt);
+        }
+        out_handle.flush()?;
+        if in_handle.read_line(&mut input)? == 0 {
+            // end of input
+            println!();
+            return Ok(());
+        }
+        if nesting_depth(&input) > 0 {
+            continue;
+        }
+
+        let input = take(&mut input);
+        // errors are part of the answer to the line, so they go to stdout too
+        execute(
+            String::from(input.trim()),
+            mode,
+            &mut env,
+            colored,
+            &mut out_handle,
+            &mut stdout(),
+        )?;
+    }
+}
+
+/// How many more `(`, `[` and `{` than closing ones there are. Going by the tokens means the
+/// ones in strings aren't counted.
+fn nesting_depth(input: &str) -> i32 {
+    Lexer::from(String::from(input))
+        .map(|token| match token {
+            Token::Limiter(
+                LimiterToken::LParen | LimiterToken::LBracket | LimiterToken::LBrace,
+            ) => 1,
+            Token::Limiter(
+                LimiterToken::RParen | LimiterToken::RBracket | LimiterToken::RBrace,
+            ) => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Runs a whole script, printing its result unless it's null. Fails if the file can't be read,
+/// doesn't parse or evaluates to an error.
+pub fn run_file(path: &str, mode: ReplMode) -> ExitCode {
+    match fs::read_to_string(path) {
+        Ok(source) => run(source, mode),
+        Err(e) => {
+            eprintln!("can't read {}: {}", path, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Like `run_file` with the script piped in.
+pub fn run_stdin(mode: ReplMode) -> ExitCode {
+    let mut source = String::new();
+    match stdin().read_to_string(&mut source) {
+        Ok(_) => run(source, mode),
+        Err(e) => {
+            eprintln!("can't read stdin: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(source: String, mode: ReplMode) -> ExitCode {
+    let mut env = Rc::new(RefCell::new(Environment::new()));
+    match execute(source, mode, &mut env, false, &mut stdout(), &mut stderr()) {
+        Ok(true) => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
+
+/// Whether the source went through `mode` without errors. Errors are written to `err` and
+/// anything else to `out`. When `colored`, errors are red and integer and string results yellow
+/// and cyan.
+fn execute(
+    source: String,
+    mode: ReplMode,
+    env: &mut Rc<RefCell<Environment>>,
+    colored: bool,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> io::Result<bool> {
+    let paint = |style: fn(&str) -> String, s: String| if colored { style(&s) } else { s };
+
+    if mode == ReplMode::Tokens {
+        match tokenize(&source) {
+            Ok(tokens) => {
+                for token in tokens {
+                    writeln!(out, "Token::{:?}", token)?;
+                }
+                return Ok(true);
+            }
+            Err(errors) => {
+                for error in errors {
+                    writeln!(err, "{}", paint(color::red, error.to_string()))?;
+                }
+                return Ok(false);
+            }
+        }
+    }
+
+    let program = match monkey_rs::parse(&source) {
+        Ok(program) => program,
+        Err(errors) => {
+            for error in errors {
+                writeln!(err, "{}", paint(color::red, error.to_string()))?;
+            }
+            return Ok(false);
+        }
+    };
+    if mode == ReplMode::Parse {
+        for statement in &program.statements {
+            writeln!(out, "{}", statement)?;
+        }
+        return Ok(true);
+    }
+
+    match eval_program(&program, env) {
+        Object::Null => (),
+        Object::Error(message) => {
+            writeln!(err, "{}", paint(color::red, format!("ERROR: {}", message)))?;
+            return Ok(false);
+        }
+        result @ Object::Integer(_) => {
+            writeln!(out, "{}", paint(color::yellow, result.to_string()))?
+        }
+        result @ Object::String(_) => writeln!(out, "{}", paint(color::cyan, result.to_string()))?,
+        result => writeln!(out, "{}", result)?,
+    }
+    Ok(true)
+}
+
+#[test]
+fn execute_test() {
+    let tests = [
+        ("let x = 2; x * 21", true, "42\n", ""),
+        ("let x = 2;", true, "", ""),
+        (
+            "let f = fn(x) {\n  return x;\n};\nf(\"a\")",
+            true,
+            "a\n",
+            "",
+        ),
+        (
+            "1 + true",
+            false,
+            "",
+            "ERROR: type mismatch: INTEGER + BOOLEAN\n",
+        ),
+        (
+            "let x 1;",
+            false,
+            "",
+            "expected next token to be `=`, got `1` instead at line 1, column 7\n",
+        ),
+    ];
+
+    for (source, ok, expected_out, expected_err) in tests {
+        let mut env = Rc::new(RefCell::new(Environment::new()));
+        let (mut out, mut err) = (vec![], vec![]);
+        assert_eq!(
+            execute(
+                String::from(source),
+                ReplMode::Eval,
+                &mut env,
+                false,
+                &mut out,
+                &mut err
+            )
+            .unwrap(),
+            ok,
+            "{}",
+            source
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected_out);
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err);
+    }
+}
+
+#[test]
+fn execute_modes_test() {
+    let tests = [
+        (
+            "let x = 5;",
+            ReplMode::Tokens,
+            true,
+            "Token::Let\nToken::Identifier(\"x\")\nToken::Assign\nToken::Literal(\"5\")\nToken::Limiter(Semicolon)\n",
+            "",
+        ),
+        (
+            "@",
+            ReplMode::Tokens,
+            false,
+            "",
+            "unexpected character '@' at line 1, column 1\n",
+        ),
+        (
+            "let x = 1 + 2 * 3; x",
+            ReplMode::Parse,
+            true,
+            "let x = (1 + (2 * 3));\nx;\n",
+            "",
+        ),
+        (
+            "let max = fn(a, b) {\n  if (a > b) { a } else { b }\n};\nmax(1, -2)",
+            ReplMode::Parse,
+            true,
+            "let max = fn(a, b) { if ((a > b)) { a; } else { b; }; };\nmax(1, (-2));\n",
+            "",
+        ),
+        (
+            "1 + true",
+            ReplMode::Parse,
+            true,
+            "(1 + true);\n",
+            "",
+        ),
+        (
+            "let x;",
+            ReplMode::Parse,
+            false,
+            "",
+            "expected next token to be `=`, got `;` instead at line 1, column 6\n",
+        ),
+    ];
+
+    for (source, mode, ok, expected_out, expected_err) in tests {
+        let mut env = Rc::new(RefCell::new(Environment::new()));
+        let (mut out, mut err) = (vec![], vec![]);
+        assert_eq!(
+            execute(
+                String::from(source),
+                mode,
+                &mut env,
+                false,
+                &mut out,
+                &mut err
+            )
+            .unwrap(),
+            ok,
+            "{}",
+            source
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected_out, "{}", source);
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err, "{}", source);
+    }
+}
+
+#[test]
+fn execute_colored_test() {
+    let tests = [
+        ("40 + 2", "\x1b[33m42\x1b[0m\n", ""),
+        ("\"a\" + \"b\"", "\x1b[36mab\x1b[0m\n", ""),
+        ("true", "true\n", ""),
+        ("-true", "", "\x1b[31mERROR: unknown operator: -BOOLEAN\x1b[0m\n"),
+        (
+            "let = 1;",
+            "",
+            "\x1b[31mexpected next token to be an identifier, got `=` instead at line 1, column 5\x1b[0m\n",
+        ),
+    ];
+
+    for (source, expected_out, expected_err) in tests {
+        let mut env = Rc::new(RefCell::new(Environment::new()));
+        let (mut out, mut err) = (vec![], vec![]);
+        execute(
+            String::from(source),
+            ReplMode::Eval,
+            &mut env,
+            true,
+            &mut out,
+            &mut err,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected_out, "{}", source);
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err, "{}", source);
+    }
+}
+
+#[test]
+fn execute_keeps_bindings_test() {
+    let mut env = Rc::new(RefCell::new(Environment::new()));
+    let (mut out, mut err) = (vec![], vec![]);
+    for source in ["let x = 1; let f = fn() { x };", "let x = 2; [f(), x]"] {
+        let ok = execute(
+            String::from(source),
+            ReplMode::Eval,
+            &mut env,
+            false,
+            &mut out,
+            &mut err,
+        );
+        assert!(ok.unwrap(), "{}", source);
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "[1, 2]\n");
+}
+
+#[test]
+fn nesting_depth_test() {
+    let tests = [
+        ("let x = 1;", 0),
+        ("let f = fn(x) {", 1),
+        ("let f = fn(x) {\n  if (x) {\n", 2),
+        ("let f = fn(x) {\n  x\n};", 0),
+        ("[1, 2,\n", 1),
+        ("add(\n  1,\n  {\"a\": [2]})", 0),
+        ("\"{(\" + \"[\"", 0),
+        ("}", -1),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(nesting_depth(input), expected, "{}", input);
     }
 }